@@ -7,15 +7,18 @@ use warp::Filter;
 
 #[derive(Serialize, Deserialize, Clone)]
 struct Client {
+    #[serde(rename = "publicKey", default, skip_serializing_if = "Option::is_none")]
+    public_key: Option<String>,
+    /// Receiver's PAKE message, the session code's secret never gets here
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pake: Option<String>,
     #[serde(rename = "webRTC")]
-    web_rtc: WebRtc,
+    web_rtc: Opaque,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-struct WebRtc {
-    offer: Option<serde_json::Value>,
-    answer: Option<serde_json::Value>,
-}
+/// Session description exactly as a peer sent it. CLI peers encrypt it with
+/// the secret half of the session code, so it is never looked into here.
+type Opaque = serde_json::Value;
 
 #[derive(Serialize, Deserialize, Clone)]
 struct Peer {
@@ -23,8 +26,11 @@ struct Peer {
     created: chrono::DateTime<chrono::Utc>,
     #[serde(rename = "publicKey")]
     public_key: String,
+    /// Sender's PAKE message, absent for web senders
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pake: Option<String>,
     #[serde(rename = "webRTC")]
-    web_rtc: WebRtc,
+    web_rtc: Opaque,
     #[serde(skip)]
     client: Option<Client>,
 }
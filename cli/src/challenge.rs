@@ -0,0 +1,318 @@
+use crate::common::InfoMessage;
+use crate::crypto::Session;
+use crate::hangman::Hangman;
+use crate::number_guess::NumberGuess;
+use crate::sudoku::Sudoku;
+use crate::typing_test::TypingTest;
+use crate::wordle::{GuessLetterResult, Wordle};
+use async_trait::async_trait;
+use log::warn;
+use rand::prelude::IndexedRandom;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use webrtc::data_channel::RTCDataChannel;
+
+pub(crate) const DEFAULT_CHALLENGE: &str = "wordle";
+pub(crate) const RANDOM: &str = "random";
+pub(crate) const DEFAULT_INTERVAL: usize = 1024 * 10; // 10 KiB
+pub(crate) const DEFAULT_ANSWER_TIMEOUT: u64 = 120; // seconds
+// Well below the idle timeouts of NATs and the peer's own patience
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How a challenge ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Outcome {
+    Pass,
+    Fail,
+    /// The user gave up or input ran out, no verdict either way
+    Abort,
+}
+
+/// Who has to pass challenges during a transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Side {
+    Sender,
+    Receiver,
+    Both,
+}
+
+/// Whether the built-in solver answers our Wordle challenges, never without the `solver` feature
+pub(crate) fn solver_enabled() -> bool {
+    #[cfg(feature = "solver")]
+    return crate::solver::enabled();
+    #[cfg(not(feature = "solver"))]
+    false
+}
+
+/// Whether the solver answers any of the `name` challenges, it only plays Wordle, which `random` may pick
+pub(crate) fn solver_plays(name: &str) -> bool {
+    solver_enabled() && matches!(name, "wordle" | RANDOM)
+}
+
+impl Side {
+    pub fn sender(self) -> bool {
+        matches!(self, Side::Sender | Side::Both)
+    }
+
+    pub fn receiver(self) -> bool {
+        matches!(self, Side::Receiver | Side::Both)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Side::Sender => "sender",
+            Side::Receiver => "receiver",
+            Side::Both => "both",
+        }
+    }
+}
+
+/// How tough challenges are, each challenge maps this onto its own knobs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const NAMES: [&'static str; 3] = ["easy", "normal", "hard"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+}
+
+/// Chosen by the sender, for its own challenges and the receiver's alike
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Settings {
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    /// Hints revealed so far have to be used, for challenges that give hints
+    #[serde(rename = "hardMode", default)]
+    pub hard_mode: bool,
+    /// Seconds to answer each prompt, no limit if none
+    #[serde(rename = "answerTimeout", default)]
+    pub answer_timeout: Option<u64>,
+}
+
+/// How far the transfer got when a challenge came up, it stays paused meanwhile
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct TransferProgress {
+    /// Bytes of file data so far, before compression
+    pub done: u64,
+    pub total: Option<u64>,
+}
+
+/// Colour patterns of scored guesses as they happen, never the letters
+pub(crate) type Guesses = mpsc::UnboundedSender<[GuessLetterResult; 5]>;
+
+/// A gate the user has to get through to keep the transfer going
+#[async_trait]
+pub(crate) trait Challenge: Send {
+    /// Shown to a peer watching the challenge
+    fn name(&self) -> &'static str;
+
+    /// Challenges with Wordle-like hints report every scored guess to `guesses`
+    async fn run(&mut self, progress: TransferProgress, guesses: &Guesses) -> Outcome;
+}
+
+/// Makes a fresh challenge every time the gate comes up
+pub(crate) type Factory = fn(&Settings) -> Box<dyn Challenge>;
+
+/// Run a fresh challenge, keeping the connection busy while the user thinks.
+/// With `announce` the peer gets to watch: which challenge, the pattern of every guess and the outcome
+pub(crate) async fn run(
+    factory: Factory,
+    settings: &Settings,
+    progress: TransferProgress,
+    channel: &RTCDataChannel,
+    session: &Session,
+    announce: bool,
+) -> Outcome {
+    let mut challenge = factory(settings);
+    if announce {
+        notify(channel, session, &InfoMessage::SenderChallengeStarted { challenge: challenge.name().to_owned() }).await;
+    }
+    let (guesses, mut scored) = mpsc::unbounded_channel();
+    let outcome = challenge.run(progress, &guesses);
+    tokio::pin!(outcome);
+    let mut keepalive = tokio::time::interval_at(tokio::time::Instant::now() + KEEPALIVE_INTERVAL, KEEPALIVE_INTERVAL);
+    let outcome = loop {
+        tokio::select! {
+            outcome = &mut outcome => break outcome,
+            Some(pattern) = scored.recv() => {
+                if announce {
+                    notify(channel, session, &InfoMessage::SenderChallengeGuess { pattern }).await;
+                }
+            }
+            _ = keepalive.tick() => notify(channel, session, &InfoMessage::KeepAlive {}).await,
+        }
+    };
+    if announce {
+        // The winning guess may come in together with the outcome
+        while let Ok(pattern) = scored.try_recv() {
+            notify(channel, session, &InfoMessage::SenderChallengeGuess { pattern }).await;
+        }
+        notify(channel, session, &InfoMessage::SenderChallengeFinished { outcome }).await;
+    }
+    outcome
+}
+
+/// Messages about a running challenge are left out of the transcript, losing one only costs the peer a line
+async fn notify(channel: &RTCDataChannel, session: &Session, info: &InfoMessage) {
+    let text = serde_json::to_string(info).unwrap();
+    if let Err(e) = channel.send_text(session.seal_text(&text)).await {
+        warn!("Error sending challenge update: {e}");
+    }
+}
+
+struct Entry {
+    name: &'static str,
+    description: &'static str,
+    factory: Factory,
+}
+
+/// Every challenge that can be picked with `--challenge`
+pub(crate) struct Registry {
+    entries: Vec<Entry>,
+}
+
+impl Registry {
+    pub fn builtin() -> Self {
+        let mut registry = Registry { entries: Vec::new() };
+        registry.register("wordle", "Guess a five-letter word", |settings| {
+            Box::new(WordleChallenge { settings: *settings })
+        });
+        registry.register("number", "Guess a number from higher or lower hints", |settings| {
+            Box::new(NumberGuessChallenge { settings: *settings })
+        });
+        registry.register("hangman", "Guess a word letter by letter", |settings| {
+            Box::new(HangmanChallenge { settings: *settings })
+        });
+        registry.register("sudoku", "Fill in a 4x4 Sudoku", |settings| Box::new(SudokuChallenge { settings: *settings }));
+        registry.register("typing", "Type a line of words against the clock", |settings| {
+            Box::new(TypingChallenge { settings: *settings })
+        });
+        registry.register(RANDOM, "A different one of the above every time", Registry::random);
+        registry
+    }
+
+    /// Picks anew on every call, so each challenge interval may bring another game
+    fn random(settings: &Settings) -> Box<dyn Challenge> {
+        let registry = Registry::builtin();
+        let games: Vec<&Entry> = registry.entries.iter().filter(|entry| entry.name != RANDOM).collect();
+        (games.choose(&mut rand::rng()).unwrap().factory)(settings)
+    }
+
+    pub fn register(&mut self, name: &'static str, description: &'static str, factory: Factory) {
+        self.entries.push(Entry { name, description, factory });
+    }
+
+    pub fn get(&self, name: &str) -> Option<Factory> {
+        self.entries.iter().find(|entry| entry.name == name).map(|entry| entry.factory)
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.entries.iter().map(|entry| entry.name).collect()
+    }
+
+    /// One line per challenge, for `--help`
+    pub fn describe(&self) -> String {
+        self.entries
+            .iter()
+            .map(|entry| format!("{}: {}", entry.name, entry.description))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+struct WordleChallenge {
+    settings: Settings,
+}
+
+#[async_trait]
+impl Challenge for WordleChallenge {
+    fn name(&self) -> &'static str {
+        "wordle"
+    }
+
+    async fn run(&mut self, progress: TransferProgress, guesses: &Guesses) -> Outcome {
+        eprintln!("To prove that you are not a robot, solve a wordle");
+        Wordle::play(&self.settings, progress, guesses).await
+    }
+}
+
+struct NumberGuessChallenge {
+    settings: Settings,
+}
+
+#[async_trait]
+impl Challenge for NumberGuessChallenge {
+    fn name(&self) -> &'static str {
+        "number"
+    }
+
+    async fn run(&mut self, _progress: TransferProgress, _guesses: &Guesses) -> Outcome {
+        eprintln!("To prove that you are not a robot, guess a number");
+        NumberGuess::play(&self.settings).await
+    }
+}
+
+struct HangmanChallenge {
+    settings: Settings,
+}
+
+#[async_trait]
+impl Challenge for HangmanChallenge {
+    fn name(&self) -> &'static str {
+        "hangman"
+    }
+
+    async fn run(&mut self, _progress: TransferProgress, _guesses: &Guesses) -> Outcome {
+        eprintln!("To prove that you are not a robot, save the hangman");
+        Hangman::play(&self.settings).await
+    }
+}
+
+struct SudokuChallenge {
+    settings: Settings,
+}
+
+#[async_trait]
+impl Challenge for SudokuChallenge {
+    fn name(&self) -> &'static str {
+        "sudoku"
+    }
+
+    async fn run(&mut self, _progress: TransferProgress, _guesses: &Guesses) -> Outcome {
+        eprintln!("To prove that you are not a robot, solve a sudoku");
+        Sudoku::play(&self.settings).await
+    }
+}
+
+struct TypingChallenge {
+    settings: Settings,
+}
+
+#[async_trait]
+impl Challenge for TypingChallenge {
+    fn name(&self) -> &'static str {
+        "typing"
+    }
+
+    async fn run(&mut self, _progress: TransferProgress, _guesses: &Guesses) -> Outcome {
+        eprintln!("To prove that you are not a robot, pass a typing test");
+        TypingTest::play(&self.settings).await
+    }
+}
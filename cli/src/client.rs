@@ -1,10 +1,23 @@
-use crate::common::InfoMessage;
-use anyhow::Result;
+use crate::challenge::{self, Outcome, TransferProgress};
+use crate::common::{ChallengePolicy, Compression, EntryKind, Frame, InfoMessage, ManifestEntry, TransferSignature};
+use crate::compression::{Decoder, Sink};
+use crate::crypto::{self, Binding, KeyPair, Session, Transcript};
+use crate::pake::Pake;
+use crate::signalling::SessionCode;
+use crate::identity::{self, Identity};
+use crate::manifest;
+use crate::pacing::{Interval, MAX_INTERVAL};
+use crate::resume::{self, Progress};
+use crate::wordle::GuessLetterResult;
+use anyhow::{anyhow, bail, Context, Result};
 use captures::capture;
-use log::{error, info, warn};
+use log::{debug, error, info, warn};
+use std::fs::{self, File, OpenOptions};
 use std::io;
-use std::io::Write;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use webrtc::api::interceptor_registry::register_default_interceptors;
 use webrtc::api::media_engine::MediaEngine;
 use webrtc::api::APIBuilder;
@@ -14,9 +27,355 @@ use webrtc::ice_transport::ice_server::RTCIceServer;
 use webrtc::interceptor::registry::Registry;
 use webrtc::peer_connection::configuration::RTCConfiguration;
 use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
-use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
 
-pub(crate) async fn main(session_id: &str) -> Result<()> {
+// How often the resume sidecar is rewritten while data arrives
+const SAVE_EVERY_BYTES: u64 = 4 * 1024 * 1024;
+const SAVE_EVERY: Duration = Duration::from_secs(2);
+
+/// State of the download in progress on a data channel
+struct Incoming {
+    /// File to write to, stdout if none
+    output: Option<PathBuf>,
+    writer: Option<Decoder>,
+    /// Set when the sender offered a resumable transfer and we write to a file
+    progress: Option<Progress>,
+    /// Contents of a multi-file transfer and where its entries go
+    entries: Vec<(ManifestEntry, PathBuf)>,
+    next_sequence: u64,
+    /// Position in the stream of frames, compressed if the sender chose to
+    next_offset: u64,
+    /// Where the current file's output started, non-zero when resuming
+    base_offset: u64,
+    /// Size of finished files of a multi-file transfer
+    finished: u64,
+    /// Size of the whole transfer, if the sender told
+    total: Option<u64>,
+    /// Bytes received since the sidecar was last written, and when that was
+    unsaved: u64,
+    saved_at: Instant,
+}
+
+impl Incoming {
+    fn new(output: Option<PathBuf>) -> Self {
+        Incoming {
+            output,
+            writer: None,
+            progress: None,
+            entries: Vec::new(),
+            next_sequence: 0,
+            next_offset: 0,
+            base_offset: 0,
+            finished: 0,
+            total: None,
+            unsaved: 0,
+            saved_at: Instant::now(),
+        }
+    }
+
+    /// How much file data has been written, for the progress bar of challenges
+    fn transferred(&self) -> TransferProgress {
+        let current = self.writer.as_ref().map_or(0, |writer| self.base_offset + writer.written());
+        TransferProgress { done: self.finished + current, total: self.total }
+    }
+
+    /// Validate every path of a multi-file transfer and create its directories
+    fn manifest(&mut self, entries: Vec<ManifestEntry>) -> Result<()> {
+        let root = self.output.clone().unwrap_or_else(|| PathBuf::from("."));
+        fs::create_dir_all(&root)?;
+        self.entries = entries
+            .into_iter()
+            .map(|entry| {
+                let path = manifest::safe_join(&root, &entry.path)?;
+                Ok((entry, path))
+            })
+            .collect::<Result<_>>()?;
+        self.total = Some(self.entries.iter().map(|(entry, _)| entry.size).sum());
+        for (entry, path) in &self.entries {
+            if entry.kind == EntryKind::Directory {
+                fs::create_dir_all(path)?;
+            }
+        }
+        println!("Receiving {} entries into {}", self.entries.len(), root.display());
+        Ok(())
+    }
+
+    fn file_begin(&mut self, index: usize, compression: Option<Compression>) -> Result<()> {
+        let Some((entry, path)) = self.entries.get(index) else {
+            bail!("Unknown manifest entry {index}");
+        };
+        if entry.kind != EntryKind::File {
+            bail!("Manifest entry {} is not a file", entry.path);
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        println!("Receiving {}", entry.path);
+        self.writer = Some(Decoder::new(compression, Sink::new(Box::new(File::create(path)?)))?);
+        self.next_sequence = 0;
+        self.next_offset = 0;
+        self.base_offset = 0;
+        Ok(())
+    }
+
+    fn file_end(&mut self, index: usize) -> Result<()> {
+        let Some((entry, path)) = self.entries.get(index) else {
+            bail!("Unknown manifest entry {index}");
+        };
+        let Some(mut writer) = self.writer.take() else {
+            bail!("File end received before file begin");
+        };
+        writer.flush()?;
+        if writer.written() != entry.size {
+            bail!("{} is {} bytes, expected {}", entry.path, writer.written(), entry.size);
+        }
+        self.finished += entry.size;
+        manifest::set_mode(path, entry.mode)
+    }
+
+    /// Prepare the output for a new transfer, returns the reply for the sender if any
+    fn begin(
+        &mut self,
+        size: Option<u64>,
+        transfer_id: Option<String>,
+        content_hash: Option<String>,
+        compression: Option<Compression>,
+    ) -> Result<Option<InfoMessage>> {
+        let resumable = transfer_id.zip(content_hash);
+        self.total = size;
+        let mut offset = 0;
+        let sink = Sink::new(match &self.output {
+            None => Box::new(io::stdout()),
+            Some(path) => {
+                if let Some((transfer_id, content_hash)) = &resumable {
+                    offset = Progress::resume_offset(path, transfer_id, content_hash);
+                    self.progress = Some(Progress {
+                        transfer_id: transfer_id.clone(),
+                        content_hash: content_hash.clone(),
+                        offset,
+                    });
+                }
+                let mut file = OpenOptions::new().create(true).write(true).truncate(false).open(path)?;
+                file.set_len(offset)?;
+                file.seek(SeekFrom::Start(offset))?;
+                Box::new(file)
+            }
+        });
+        if offset > 0 {
+            println!("Resuming transfer at byte {offset}");
+        }
+        self.writer = Some(Decoder::new(compression, sink)?);
+        self.next_sequence = 0;
+        self.next_offset = offset;
+        self.base_offset = offset;
+        Ok(resumable.map(|_| InfoMessage::Resume { offset }))
+    }
+
+    fn frame(&mut self, frame: Frame) -> Result<()> {
+        let Some(writer) = self.writer.as_mut() else {
+            bail!("Data received before begin message");
+        };
+        if frame.sequence < self.next_sequence {
+            warn!("Dropping duplicate frame {}", frame.sequence);
+            return Ok(());
+        }
+        if frame.sequence != self.next_sequence || frame.offset != self.next_offset {
+            bail!(
+                "Out of order frame: expected #{} at {}, got #{} at {}",
+                self.next_sequence, self.next_offset, frame.sequence, frame.offset
+            );
+        }
+        writer.write_all(&frame.payload)?;
+        writer.flush()?;
+        self.next_sequence += 1;
+        self.next_offset += frame.payload.len() as u64;
+        if let Some(progress) = self.progress.as_mut() {
+            progress.offset = self.base_offset + writer.written();
+        }
+        self.unsaved += frame.payload.len() as u64;
+        if self.unsaved >= SAVE_EVERY_BYTES || self.saved_at.elapsed() >= SAVE_EVERY {
+            self.save_progress()?;
+        }
+        Ok(())
+    }
+
+    /// Write the sidecar, a stale one only costs a few frames since resuming truncates to its offset
+    fn save_progress(&mut self) -> Result<()> {
+        self.unsaved = 0;
+        self.saved_at = Instant::now();
+        if let (Some(progress), Some(path)) = (&self.progress, &self.output) {
+            progress.save(path)?;
+        }
+        Ok(())
+    }
+
+    fn end(&mut self) -> Result<()> {
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush()?;
+        }
+        // Directory permissions last, a read-only directory would have blocked its contents
+        for (entry, path) in self.entries.iter().rev() {
+            if entry.kind == EntryKind::Directory {
+                manifest::set_mode(path, entry.mode)?;
+            }
+        }
+        if let (Some(progress), Some(path)) = (self.progress.take(), &self.output) {
+            let actual = resume::content_hash(&mut File::open(path)?)?;
+            if actual != progress.content_hash {
+                bail!("Content hash mismatch: expected {}, got {actual}", progress.content_hash);
+            }
+            Progress::remove(path)?;
+        }
+        Ok(())
+    }
+
+    /// Move the received files aside after a failed end, so they can not pass for a checked transfer
+    fn reject(&mut self) -> Result<()> {
+        self.writer = None;
+        self.progress = None;
+        let files = self.entries.iter().filter(|(entry, _)| entry.kind == EntryKind::File).map(|(_, path)| path);
+        for path in self.output.iter().chain(files) {
+            if path.is_file() {
+                Progress::remove(path)?;
+                let mut rejected = path.as_os_str().to_owned();
+                rejected.push(".rejected");
+                fs::rename(path, &rejected)?;
+                warn!("Kept the rejected data as {}", PathBuf::from(rejected).display());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Incoming {
+    /// Keep the latest offset when the transfer stops without an end message
+    fn drop(&mut self) {
+        if let Err(e) = self.save_progress() {
+            warn!("Could not save transfer progress: {e}");
+        }
+    }
+}
+
+/// Tell the user who sent the transfer, trusting unknown senders on first use
+fn check_sender(signature: Option<TransferSignature>, hash: &str) -> Result<()> {
+    let Some(signature) = signature else {
+        warn!("Transfer is not signed, the sender can not be identified");
+        return Ok(());
+    };
+    let sender = identity::verify_transfer(&signature, hash)?;
+    match identity::known_sender(&sender)? {
+        Some(name) => eprintln!("Signed by {name}"),
+        None => {
+            let name = identity::remember_sender(&sender)?;
+            eprintln!("WARNING: first transfer from unknown sender {}", signature.sender);
+            eprintln!("Make sure this is the sender's key, it is now trusted as {name} in the known senders file");
+        }
+    }
+    Ok(())
+}
+
+/// Tell the user whether the sender enforced challenges, as it recorded in the signed metadata
+fn report_challenges(policy: Option<&ChallengePolicy>) {
+    match policy {
+        Some(ChallengePolicy { enforced: false, .. }) => {
+            eprintln!("NOTE: the sender turned challenges off with --no-challenges");
+        }
+        Some(ChallengePolicy { challenge: Some(challenge), side: Some(side), .. }) => {
+            eprintln!("Challenges enforced: {challenge}, side: {}", side.name());
+        }
+        Some(_) | None => info!("Sender did not record whether challenges were enforced"),
+    }
+    match policy {
+        Some(ChallengePolicy { automated: true, challenge: Some(challenge), .. }) if challenge == challenge::RANDOM => {
+            eprintln!("NOTE: whenever random picks Wordle for the sender, a solver plays it (--solve-challenges), not a human");
+        }
+        Some(ChallengePolicy { automated: true, .. }) => {
+            eprintln!("NOTE: the sender's Wordle challenges are played by a solver (--solve-challenges), not a human");
+        }
+        _ => {}
+    }
+}
+
+/// One line of the spectator view: what the sender's guess scored, the letters stay hidden.
+/// Squares like shared Wordle results, they read the same without colours
+fn spectate_guess(number: usize, pattern: &[GuessLetterResult; 5]) -> String {
+    let tiles: String = pattern
+        .iter()
+        .map(|tile| match tile {
+            GuessLetterResult::Green => '🟩',
+            GuessLetterResult::Yellow => '🟨',
+            GuessLetterResult::Gray => '⬛',
+        })
+        .collect();
+    format!("Sender's guess {number}: {tiles}")
+}
+
+async fn send_info(channel: &RTCDataChannel, session: &Session, info: &InfoMessage) -> Result<()> {
+    channel.send_text(session.seal_text(&serde_json::to_string(info)?)).await?;
+    Ok(())
+}
+
+/// Answer the sender's password challenge, asking the user unless `--password` was given
+async fn prove_password(channel: &RTCDataChannel, session: &Session, password: Option<String>, salt: &str) -> Result<()> {
+    let password = match password {
+        Some(password) => password,
+        None => tokio::task::spawn_blocking(|| rpassword::prompt_password("The sender requires a password: ")).await??,
+    };
+    let proof = session.password_proof(&password, salt)?;
+    send_info(channel, session, &InfoMessage::PasswordProof { proof }).await
+}
+
+/// Challenge the sender wants us to pass every `interval` bytes
+struct Gate {
+    challenge: challenge::Factory,
+    settings: challenge::Settings,
+    interval: Interval,
+    bytes_until: u64,
+}
+
+impl Gate {
+    /// Count received bytes, true when the challenge is due
+    fn advance(&mut self, bytes: u64) -> bool {
+        self.bytes_until = self.bytes_until.saturating_sub(bytes);
+        if self.bytes_until > 0 {
+            return false;
+        }
+        self.bytes_until = self.interval.sample(&mut rand::rng());
+        true
+    }
+}
+
+/// Pause the sender while the user solves a challenge, nothing more is read meanwhile
+async fn run_gate(
+    channel: &RTCDataChannel,
+    session: &Session,
+    challenge: challenge::Factory,
+    settings: challenge::Settings,
+    progress: TransferProgress,
+) -> Result<Outcome> {
+    send_info(channel, session, &InfoMessage::ChallengeStarted {}).await?;
+    eprintln!("Suspicious activity detected");
+    let outcome = challenge::run(challenge, &settings, progress, channel, session, false).await;
+    send_info(channel, session, &InfoMessage::ChallengeFinished { outcome }).await?;
+    Ok(outcome)
+}
+
+pub(crate) async fn main(code: &SessionCode, output: Option<PathBuf>, password: Option<String>) -> Result<()> {
+    let signalling = crate::signalling::SignallingClient::new("http://64.188.74.63".to_owned());
+    let offer_to = signalling.get_offer(code).await?;
+
+    let key_pair = KeyPair::generate();
+    let peer_key = crypto::parse_public_key(&offer_to.peer.public_key)?;
+    let pake = match (&code.secret, &offer_to.peer.pake) {
+        (Some(secret), Some(peer_pake)) => Some((Pake::start(secret, peer_key.as_bytes()), peer_pake.clone())),
+        (Some(_), None) => bail!("Sender did not start a key exchange for this code, refusing to connect"),
+        (None, Some(_)) => bail!("Session code is missing its secret part, ask the sender for the full code"),
+        (None, None) => {
+            warn!("Session code has no secret part, the signalling server could intercept this transfer");
+            None
+        }
+    };
+    let identity = if pake.is_some() { Some(Identity::load_or_create()?) } else { None };
+
     let mut m = MediaEngine::default();
     m.register_default_codecs()?;
     let mut registry = Registry::new();
@@ -45,7 +404,6 @@ pub(crate) async fn main(session_id: &str) -> Result<()> {
             ],
             username: "6fb0f47d8cb4265a38814e9d".to_owned(),
             credential: "fgSXLhtt0s2cUy9C".to_owned(),
-            ..Default::default()
         }],
         ..Default::default()
     };
@@ -53,7 +411,7 @@ pub(crate) async fn main(session_id: &str) -> Result<()> {
     // Create a new RTCPeerConnection
     let peer_connection = Arc::new(api.new_peer_connection(config).await?);
 
-    let (done_tx, mut done_rx) = tokio::sync::mpsc::channel::<()>(1);
+    let (done_tx, mut done_rx) = tokio::sync::mpsc::channel::<Result<()>>(1);
 
     // Set the handler for Peer connection state
     // This will notify you when the peer has connected/disconnected
@@ -64,13 +422,51 @@ pub(crate) async fn main(session_id: &str) -> Result<()> {
             // Wait until PeerConnection has had no network activity for 30 seconds or another failure. It may be reconnected using an ICE Restart.
             // Use webrtc.PeerConnectionStateDisconnected if you are interested in detecting faster timeout.
             // Note that the PeerConnection may come back from PeerConnectionStateDisconnected.
-            error!("Peer Connection has gone to failed exiting");
-            let _ = done_tx.try_send(());
+            let _ = done_tx.try_send(Err(anyhow!("Peer connection failed")));
         }
 
         Box::pin(async {})
     })));
 
+    // Wait for the offer to be pasted
+    let offer = offer_to.peer.web_rtc.open(code.secret.as_deref())?.offer;
+    let offer_sdp = offer.sdp.clone();
+
+    // Set the remote SessionDescription
+    peer_connection.set_remote_description(offer).await?;
+
+    // Create an answer
+    let answer = peer_connection.create_answer(None).await?;
+
+    // Create channel that is blocked until ICE Gathering is complete
+    let mut gather_complete = peer_connection.gathering_complete_promise().await;
+
+    // Sets the LocalDescription, and starts our UDP listeners
+    peer_connection.set_local_description(answer).await?;
+
+    // Block until ICE Gathering is complete, disabling trickle ICE
+    // we do this because we only can exchange one signaling message
+    // in a production application you should exchange ICE Candidates via OnICECandidate
+    let _ = gather_complete.recv().await;
+
+    let local_desc = peer_connection
+        .local_description()
+        .await
+        .expect("generate local_description failed!");
+
+    // Everything on the data channel is encrypted with a key only the two peers know,
+    // bound to the code's secret and to both DTLS certificates when there is one
+    let binding = match &pake {
+        Some((pake, peer_pake)) => {
+            let pake_key = pake.finish(peer_pake, peer_pake, &pake.message_base64())?;
+            Some(Binding::new(pake_key, &offer_sdp, &local_desc.sdp)?)
+        }
+        None => None,
+    };
+    let session = Arc::new(Session::new(
+        &key_pair.session_key(&peer_key, &peer_key, &key_pair.public, binding.as_ref())?,
+    ));
+
     // Register data channel creation handling
     peer_connection
         .on_data_channel(Box::new(capture!(with done_tx = done_tx.clone(), move |d: Arc<RTCDataChannel>| {
@@ -81,11 +477,11 @@ pub(crate) async fn main(session_id: &str) -> Result<()> {
             // Register channel opening handling
             let d_label2 = d_label.clone();
             let d_id2 = d_id;
-            d.on_close(Box::new(capture!(clone done_tx, move || {
+            d.on_close(Box::new(move || {
                 info!("Data channel closed");
                 // let _ = done_tx.try_send(());
                 Box::pin(async {})
-            })));
+            }));
 
             d.on_open(Box::new(move || {
                 info!("Data channel '{d_label2}'-'{d_id2}' open");
@@ -93,74 +489,202 @@ pub(crate) async fn main(session_id: &str) -> Result<()> {
             }));
 
             // Register text message handling
-            // let mut expected_size: usize = 0;
-            // let mut received_size: usize = 0;
-            d.on_message(Box::new(capture!(clone done_tx, move |msg: DataChannelMessage| {
+            let mut incoming = Incoming::new(output.clone());
+            let mut transcript = Transcript::default();
+            let mut gate: Option<Gate> = None;
+            let mut sender_guesses = 0;
+            d.on_message(Box::new(capture!(clone done_tx, clone d, clone session, clone password, move |msg: DataChannelMessage| {
                 if msg.is_string {
-                    let info: InfoMessage = serde_json::from_slice(&msg.data[..]).unwrap();
+                    let info = session.open_text(&msg.data).and_then(|data| {
+                        let info = serde_json::from_slice::<InfoMessage>(&data)?;
+                        let unrecorded = matches!(
+                            info,
+                            InfoMessage::End { .. }
+                                | InfoMessage::KeepAlive {}
+                                | InfoMessage::SenderChallengeStarted { .. }
+                                | InfoMessage::SenderChallengeGuess { .. }
+                                | InfoMessage::SenderChallengeFinished { .. }
+                        );
+                        if !unrecorded {
+                            transcript.record_text(&data);
+                        }
+                        Ok(info)
+                    });
+                    let info = match info {
+                        Ok(info) => info,
+                        Err(e) => {
+                            let _ = done_tx.try_send(Err(anyhow!("Malformed control message: {e}")));
+                            return Box::pin(async {});
+                        }
+                    };
                     match info {
-                        InfoMessage::Begin { fileinfo } => {
+                        InfoMessage::Begin { fileinfo, transfer_id, content_hash, compression, challenges } => {
                             info!("Begin: name: {}, size: {:?}", fileinfo.filename, fileinfo.filesize);
-                            // expected_size = fileinfo.filesize.unwrap();
+                            report_challenges(challenges.as_ref());
+                            match incoming.begin(fileinfo.filesize.map(|size| size as u64), transfer_id, content_hash, compression) {
+                                Ok(Some(reply)) => {
+                                    let d = d.clone();
+                                    let reply = session.seal_text(&serde_json::to_string(&reply).unwrap());
+                                    return Box::pin(async move {
+                                        if let Err(e) = d.send_text(reply).await {
+                                            error!("Error sending resume message: {e}");
+                                        }
+                                    });
+                                }
+                                Ok(None) => {}
+                                Err(e) => {
+                                    let _ = done_tx.try_send(Err(anyhow!("Error preparing output: {e}")));
+                                }
+                            }
+                        }
+                        InfoMessage::Manifest { entries, challenges } => {
+                            report_challenges(challenges.as_ref());
+                            if let Err(e) = incoming.manifest(entries) {
+                                let _ = done_tx.try_send(Err(anyhow!("Rejecting transfer: {e}")));
+                            }
+                        }
+                        InfoMessage::FileBegin { index, compression } => {
+                            if let Err(e) = incoming.file_begin(index, compression) {
+                                let _ = done_tx.try_send(Err(anyhow!("Error preparing output: {e}")));
+                            }
+                        }
+                        InfoMessage::FileEnd { index } => {
+                            if let Err(e) = incoming.file_end(index) {
+                                let _ = done_tx.try_send(Err(anyhow!("Error finishing file: {e}")));
+                            }
+                        }
+                        InfoMessage::End { signature } => {
+                            let result = incoming.end().context("Transfer incomplete")
+                                .and_then(|()| check_sender(signature, &transcript.hash()).context("Transfer is not authentic"));
+                            if result.is_err() && let Err(e) = incoming.reject() {
+                                warn!("Could not move the rejected data aside: {e}");
+                            }
+                            let _ = done_tx.try_send(result);
                         }
-                        InfoMessage::End { .. } => {
-                            done_tx.try_send(()).unwrap();
+                        InfoMessage::PasswordRequired { salt } => {
+                            let (d, session, done_tx, password) = (d.clone(), session.clone(), done_tx.clone(), password.clone());
+                            return Box::pin(async move {
+                                if let Err(e) = prove_password(&d, &session, password, &salt).await {
+                                    let _ = done_tx.try_send(Err(anyhow!("Error answering password challenge: {e}")));
+                                }
+                            });
+                        }
+                        InfoMessage::PasswordResult { accepted: true } => {
+                            info!("Password accepted");
+                        }
+                        InfoMessage::PasswordResult { accepted: false } => {
+                            let _ = done_tx.try_send(Err(anyhow!("Wrong password, the sender aborted the transfer")));
+                        }
+                        InfoMessage::ReceiverChallenge { challenge, interval, distribution, settings } => {
+                            match challenge::Registry::builtin().get(&challenge) {
+                                Some(factory) if (1..=MAX_INTERVAL).contains(&interval) => {
+                                    eprintln!("The sender wants you to pass a {challenge} challenge every {interval} bytes on average");
+                                    let interval = Interval { distribution, mean: interval };
+                                    let bytes_until = interval.sample(&mut rand::rng());
+                                    gate = Some(Gate { challenge: factory, settings, interval, bytes_until });
+                                }
+                                Some(_) => {
+                                    let _ = done_tx.try_send(Err(anyhow!("Sender asked for a challenge every {interval} bytes, which is out of range")));
+                                }
+                                None => {
+                                    let _ = done_tx.try_send(Err(anyhow!("Sender asked for an unknown challenge {challenge}")));
+                                }
+                            }
+                        }
+                        InfoMessage::KeepAlive {} => {
+                            debug!("Sender is busy with a challenge");
+                        }
+                        InfoMessage::SenderChallengeStarted { challenge } => {
+                            sender_guesses = 0;
+                            eprintln!("The sender is solving a {challenge} challenge, data resumes once it is done");
+                        }
+                        InfoMessage::SenderChallengeGuess { pattern } => {
+                            sender_guesses += 1;
+                            eprintln!("{}", spectate_guess(sender_guesses, &pattern));
+                        }
+                        InfoMessage::SenderChallengeFinished { outcome } => match outcome {
+                            Outcome::Pass => eprintln!("The sender passed its challenge"),
+                            Outcome::Fail => eprintln!("The sender failed its challenge, the transfer will stop"),
+                            Outcome::Abort => eprintln!("The sender gave up on its challenge"),
+                        },
+                        InfoMessage::Resume { .. }
+                        | InfoMessage::PasswordProof { .. }
+                        | InfoMessage::ChallengeStarted {}
+                        | InfoMessage::ChallengeFinished { .. } => {
+                            warn!("Unexpected message from sender");
                         }
                     }
                     return Box::pin(async {});
                 }
                 let sz = msg.data.len();
                 info!("Message from DataChannel '{d_label}': '{sz}'");
-                io::stdout().write_all(&msg.data).unwrap();
-                io::stdout().flush().unwrap();
-                // received_size += sz;
+                let result = session.open(&msg.data)
+                    .and_then(|data| {
+                        transcript.record_binary(&data);
+                        Frame::decode(data)
+                    })
+                    .and_then(|frame| {
+                        let len = frame.payload.len() as u64;
+                        incoming.frame(frame).map(|_| len)
+                    });
+                match result {
+                    Ok(len) => {
+                        let due = gate.as_mut().and_then(|gate| gate.advance(len).then_some((gate.challenge, gate.settings)));
+                        if let Some((challenge, settings)) = due {
+                            let progress = incoming.transferred();
+                            let (d, session, done_tx) = (d.clone(), session.clone(), done_tx.clone());
+                            return Box::pin(async move {
+                                match run_gate(&d, &session, challenge, settings, progress).await {
+                                    Ok(Outcome::Pass) => eprintln!("Alright, looks like you are human"),
+                                    Ok(outcome) => {
+                                        let _ = done_tx.try_send(Err(anyhow!("Challenge not passed ({outcome:?}), terminating transfer")));
+                                    }
+                                    Err(e) => {
+                                        let _ = done_tx.try_send(Err(anyhow!("Error running challenge: {e}")));
+                                    }
+                                }
+                            });
+                        }
+                    }
+                    Err(e) => {
+                        if let Err(e) = incoming.save_progress() {
+                            warn!("Could not save transfer progress: {e}");
+                        }
+                        let _ = done_tx.try_send(Err(anyhow!("Error receiving data: {e}")));
+                    }
+                }
                 Box::pin(async {})
             })));
             Box::pin(async {})
         })));
 
-    // Wait for the offer to be pasted
-
-    let signalling = crate::signalling::SignallingClient::new("http://64.188.74.63".to_owned());
-    let offer_to = signalling.get_offer(session_id).await?;
-    let offer = offer_to.peer.web_rtc.offer;
-
-    // Set the remote SessionDescription
-    peer_connection.set_remote_description(offer).await?;
-
-    // Create an answer
-    let answer = peer_connection.create_answer(None).await?;
-
-    // Create channel that is blocked until ICE Gathering is complete
-    let mut gather_complete = peer_connection.gathering_complete_promise().await;
-
-    // Sets the LocalDescription, and starts our UDP listeners
-    peer_connection.set_local_description(answer).await?;
-
-    // Block until ICE Gathering is complete, disabling trickle ICE
-    // we do this because we only can exchange one signaling message
-    // in a production application you should exchange ICE Candidates via OnICECandidate
-    let _ = gather_complete.recv().await;
-
     // Output the answer in base64 so we can paste it in browser
-    if let Some(local_desc) = peer_connection.local_description().await {
-        signalling.post_answer(session_id.to_owned(), local_desc).await?;
-        info!("post answer success");
-    } else {
-        panic!("generate local_description failed!");
-    }
+    // Stdout may carry the received data, keep the code out of it
+    let verification = crypto::verification_code(&offer_sdp, &local_desc.sdp, &peer_key, &key_pair.public)?;
+    eprintln!("Verification code: {verification}");
+    eprintln!("Make sure the sender sees the same code");
+    let pake_message = pake.as_ref().map(|(pake, _)| pake.message_base64());
+    // Web senders only understand a bare key, CLI senders may restrict who they talk to
+    let public_key = match &identity {
+        Some(identity) => key_pair.public_with_identity(identity, &peer_key),
+        None => key_pair.public_base64(),
+    };
+    signalling.post_answer(code, local_desc, public_key, pake_message).await?;
+    info!("post answer success");
 
     warn!("Press ctrl-c to stop");
-    tokio::select! {
-        _ = done_rx.recv() => {
+    let result = tokio::select! {
+        result = done_rx.recv() => {
             info!("received done signal!");
+            result.unwrap_or(Ok(()))
         }
         _ = tokio::signal::ctrl_c() => {
             println!();
+            Ok(())
         }
     };
 
     peer_connection.close().await?;
 
-    Ok(())
+    result
 }
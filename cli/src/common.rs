@@ -1,3 +1,8 @@
+use crate::challenge::{Outcome, Settings, Side};
+use crate::pacing::Distribution;
+use crate::wordle::GuessLetterResult;
+use anyhow::{bail, Result};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use serde::{Serialize, Deserialize};
 
 #[derive(Serialize, Deserialize)]
@@ -8,15 +13,280 @@ pub(crate) struct FileInfo {
     pub filesize: Option<usize>,
 }
 
+/// Stream compression applied to a file's data frames
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Compression {
+    #[serde(rename = "zstd")]
+    Zstd,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EntryKind {
+    #[serde(rename = "file")]
+    File,
+    #[serde(rename = "directory")]
+    Directory,
+}
+
+/// One file or directory of a multi-file transfer
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct ManifestEntry {
+    /// Relative, `/`-separated path
+    pub path: String,
+    #[serde(rename = "type")]
+    pub kind: EntryKind,
+    pub size: u64,
+    /// Unix permission bits
+    pub mode: u32,
+}
+
+/// Sender's long-term identity vouching for everything it sent
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct TransferSignature {
+    /// Identity key of the sender
+    pub sender: String,
+    /// Hex SHA-256 over every message before `end`, see `crypto::Transcript`
+    pub hash: String,
+    pub signature: String,
+}
+
+/// Which challenges guard a transfer, signed along with the rest of the metadata
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ChallengePolicy {
+    /// False when the sender turned challenges off with `--no-challenges`
+    pub enforced: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub challenge: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub side: Option<Side>,
+    /// The sender's Wordle challenges are played by the built-in solver, see `--solve-challenges`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub automated: bool,
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub(crate) enum InfoMessage {
     #[serde(rename = "begin")]
     Begin {
         #[serde(rename = "fileInfo")]
-        fileinfo: FileInfo
+        fileinfo: FileInfo,
+        /// Present when the sender can seek its input and is willing to resume
+        #[serde(rename = "transferId", default, skip_serializing_if = "Option::is_none")]
+        transfer_id: Option<String>,
+        #[serde(rename = "contentHash", default, skip_serializing_if = "Option::is_none")]
+        content_hash: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        compression: Option<Compression>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        challenges: Option<ChallengePolicy>,
+    },
+
+    /// Receiver's reply to a resumable `Begin`: where the sender should start
+    #[serde(rename = "resume")]
+    Resume {
+        offset: u64,
+    },
+
+    /// Announces a multi-file transfer, sent instead of `Begin`
+    #[serde(rename = "manifest")]
+    Manifest {
+        entries: Vec<ManifestEntry>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        challenges: Option<ChallengePolicy>,
+    },
+
+    /// Data frames that follow belong to this manifest entry
+    #[serde(rename = "fileBegin")]
+    FileBegin {
+        index: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        compression: Option<Compression>,
+    },
+
+    #[serde(rename = "fileEnd")]
+    FileEnd {
+        index: usize,
     },
 
     #[serde(rename = "end")]
-    End {},
+    End {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        signature: Option<TransferSignature>,
+    },
+
+    /// Sender asks the receiver to prove it knows the session password
+    #[serde(rename = "passwordRequired")]
+    PasswordRequired {
+        salt: String,
+    },
+
+    /// Receiver's answer to `PasswordRequired`
+    #[serde(rename = "passwordProof")]
+    PasswordProof {
+        proof: String,
+    },
+
+    /// Sender's verdict, the transfer is aborted unless accepted
+    #[serde(rename = "passwordResult")]
+    PasswordResult {
+        accepted: bool,
+    },
+
+    /// Sender wants the receiver to pass `challenge` every `interval` bytes on average
+    #[serde(rename = "receiverChallenge")]
+    ReceiverChallenge {
+        challenge: String,
+        interval: u64,
+        #[serde(default)]
+        distribution: Distribution,
+        #[serde(default)]
+        settings: Settings,
+    },
+
+    /// Receiver is busy with a challenge, the sender pauses until it finishes
+    #[serde(rename = "challengeStarted")]
+    ChallengeStarted {},
+
+    /// Sent by whoever is solving a challenge so the connection never goes idle,
+    /// left out of the transcript
+    #[serde(rename = "keepAlive")]
+    KeepAlive {},
+
+    /// The transfer only goes on if the receiver passed
+    #[serde(rename = "challengeFinished")]
+    ChallengeFinished {
+        outcome: Outcome,
+    },
+
+    /// Sender is busy with a challenge, so the receiver knows why data stopped.
+    /// This and the two below are left out of the transcript like `KeepAlive`
+    #[serde(rename = "senderChallengeStarted")]
+    SenderChallengeStarted {
+        challenge: String,
+    },
+
+    /// Colours of a scored guess in the sender's challenge, without its letters
+    #[serde(rename = "senderChallengeGuess")]
+    SenderChallengeGuess {
+        pattern: [GuessLetterResult; 5],
+    },
+
+    #[serde(rename = "senderChallengeFinished")]
+    SenderChallengeFinished {
+        outcome: Outcome,
+    },
+}
+
+pub(crate) const FRAME_VERSION: u8 = 1;
+// version (1) + sequence (8) + offset (8) + length (4)
+pub(crate) const FRAME_HEADER_LEN: usize = 21;
+
+/// A chunk of file data as it travels over the data channel.
+///
+/// Every binary message is prefixed with a big-endian header so the receiver
+/// can detect gaps, duplicates and reordering even on an unordered channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Frame {
+    pub sequence: u64,
+    pub offset: u64,
+    pub payload: Bytes,
+}
+
+impl Frame {
+    pub fn encode(&self) -> Bytes {
+        let mut buf = BytesMut::with_capacity(FRAME_HEADER_LEN + self.payload.len());
+        buf.put_u8(FRAME_VERSION);
+        buf.put_u64(self.sequence);
+        buf.put_u64(self.offset);
+        buf.put_u32(self.payload.len() as u32);
+        buf.put_slice(&self.payload);
+        buf.freeze()
+    }
+
+    pub fn decode(mut data: Bytes) -> Result<Frame> {
+        if data.len() < FRAME_HEADER_LEN {
+            bail!("Frame too short: {} bytes", data.len());
+        }
+        let version = data.get_u8();
+        if version != FRAME_VERSION {
+            bail!("Unsupported frame version {version}");
+        }
+        let sequence = data.get_u64();
+        let offset = data.get_u64();
+        let len = data.get_u32() as usize;
+        if data.len() != len {
+            bail!("Frame length mismatch: header says {len}, got {}", data.len());
+        }
+        Ok(Frame { sequence, offset, payload: data })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_roundtrip() {
+        let frame = Frame {
+            sequence: 42,
+            offset: 16 * 1024 * 42,
+            payload: Bytes::from_static(b"hello, world"),
+        };
+        let encoded = frame.encode();
+        assert_eq!(encoded.len(), FRAME_HEADER_LEN + 12);
+        assert_eq!(Frame::decode(encoded).unwrap(), frame);
+    }
+
+    #[test]
+    fn frame_empty_payload() {
+        let frame = Frame { sequence: 0, offset: 0, payload: Bytes::new() };
+        assert_eq!(Frame::decode(frame.encode()).unwrap(), frame);
+    }
+
+    #[test]
+    fn frame_layout() {
+        let frame = Frame { sequence: 1, offset: 2, payload: Bytes::from_static(b"ab") };
+        let expected: &[u8] = &[
+            FRAME_VERSION,
+            0, 0, 0, 0, 0, 0, 0, 1,
+            0, 0, 0, 0, 0, 0, 0, 2,
+            0, 0, 0, 2,
+            b'a', b'b',
+        ];
+        assert_eq!(&frame.encode()[..], expected);
+    }
+
+    #[test]
+    fn frame_rejects_short_header() {
+        assert!(Frame::decode(Bytes::from_static(&[FRAME_VERSION, 0, 0])).is_err());
+    }
+
+    #[test]
+    fn frame_rejects_unknown_version() {
+        let mut encoded = BytesMut::from(&Frame {
+            sequence: 0,
+            offset: 0,
+            payload: Bytes::from_static(b"x"),
+        }.encode()[..]);
+        encoded[0] = FRAME_VERSION + 1;
+        assert!(Frame::decode(encoded.freeze()).is_err());
+    }
+
+    #[test]
+    fn frame_rejects_length_mismatch() {
+        let encoded = Frame { sequence: 0, offset: 0, payload: Bytes::from_static(b"abc") }.encode();
+        assert!(Frame::decode(encoded.slice(..encoded.len() - 1)).is_err());
+    }
+
+    #[test]
+    fn sender_guess_carries_only_colours() {
+        use GuessLetterResult::{Gray, Green, Yellow};
+        let guess = InfoMessage::SenderChallengeGuess { pattern: [Green, Yellow, Gray, Gray, Green] };
+        let text = serde_json::to_string(&guess).unwrap();
+        assert_eq!(
+            text,
+            r#"{"type":"senderChallengeGuess","pattern":["green","yellow","gray","gray","green"]}"#
+        );
+    }
 }
@@ -0,0 +1,182 @@
+use crate::common::Compression;
+use anyhow::Result;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+const ZSTD_LEVEL: i32 = 3;
+
+/// Pick the compression for a file, skipping formats that are compressed already
+pub(crate) fn choose(requested: Option<Compression>, name: &str) -> Option<Compression> {
+    let requested = requested?;
+    match mime_guess::from_path(name).first() {
+        Some(mime) if is_compressed(mime.essence_str()) => None,
+        _ => Some(requested),
+    }
+}
+
+fn is_compressed(mime: &str) -> bool {
+    let (kind, subtype) = mime.split_once('/').unwrap_or((mime, ""));
+    match kind {
+        "image" => subtype != "svg+xml" && subtype != "bmp",
+        "audio" => subtype != "wav" && subtype != "x-wav",
+        "video" => true,
+        "application" => matches!(
+            subtype,
+            "zip" | "gzip" | "x-gzip" | "x-bzip2" | "x-xz" | "zstd" | "x-7z-compressed"
+                | "vnd.rar" | "x-rar-compressed" | "java-archive" | "epub+zip" | "pdf"
+                | "vnd.openxmlformats-officedocument.wordprocessingml.document"
+                | "vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+                | "vnd.openxmlformats-officedocument.presentationml.presentation"
+        ),
+        "font" => subtype == "woff" || subtype == "woff2",
+        _ => false,
+    }
+}
+
+/// Wrap the sender's input so that reading yields the bytes to put on the wire
+pub(crate) fn reader<'a, R: Read + Send + 'a>(
+    compression: Option<Compression>,
+    input: R,
+) -> Result<Box<dyn Read + Send + 'a>> {
+    Ok(match compression {
+        None => Box::new(input),
+        Some(Compression::Zstd) => Box::new(zstd::stream::read::Encoder::new(input, ZSTD_LEVEL)?),
+    })
+}
+
+/// Counts the bytes taken from the input, i.e. before compression.
+/// Shared, since the encoder holds on to the source while it is read
+pub(crate) struct Source<R> {
+    inner: R,
+    read: Arc<AtomicU64>,
+}
+
+impl<R> Source<R> {
+    pub fn new(inner: R, read: Arc<AtomicU64>) -> Self {
+        Source { inner, read }
+    }
+}
+
+impl<R: Read> Read for Source<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+}
+
+/// Counts the bytes that reach the destination, i.e. after decompression
+pub(crate) struct Sink {
+    inner: Box<dyn Write + Send + Sync>,
+    pub written: u64,
+}
+
+impl Sink {
+    pub fn new(inner: Box<dyn Write + Send + Sync>) -> Self {
+        Sink { inner, written: 0 }
+    }
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Receiver side counterpart of `reader`
+pub(crate) enum Decoder {
+    Plain(Sink),
+    Zstd(zstd::stream::write::Decoder<'static, Sink>),
+}
+
+impl Decoder {
+    pub fn new(compression: Option<Compression>, sink: Sink) -> Result<Self> {
+        Ok(match compression {
+            None => Decoder::Plain(sink),
+            Some(Compression::Zstd) => Decoder::Zstd(zstd::stream::write::Decoder::new(sink)?),
+        })
+    }
+
+    /// Decoded bytes written so far
+    pub fn written(&self) -> u64 {
+        match self {
+            Decoder::Plain(sink) => sink.written,
+            Decoder::Zstd(decoder) => decoder.get_ref().written,
+        }
+    }
+}
+
+impl Write for Decoder {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Decoder::Plain(sink) => sink.write(buf),
+            Decoder::Zstd(decoder) => decoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Decoder::Plain(sink) => sink.flush(),
+            Decoder::Zstd(decoder) => decoder.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Lets the test look at what went through a `Sink`
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn zstd_round_trip() {
+        let original: Vec<u8> = b"instant pastebin ".iter().copied().cycle().take(100_000).collect();
+        let read = Arc::new(AtomicU64::new(0));
+        let mut wire = Vec::new();
+        reader(Some(Compression::Zstd), Source::new(&original[..], read.clone())).unwrap().read_to_end(&mut wire).unwrap();
+        assert!(wire.len() < original.len());
+        assert_eq!(read.load(Ordering::Relaxed), original.len() as u64);
+
+        let output = Shared::default();
+        let mut decoder = Decoder::new(Some(Compression::Zstd), Sink::new(Box::new(output.clone()))).unwrap();
+        // Frame sized pieces, like they arrive from the data channel
+        for chunk in wire.chunks(1000) {
+            decoder.write_all(chunk).unwrap();
+        }
+        decoder.flush().unwrap();
+        assert_eq!(decoder.written(), original.len() as u64);
+        assert_eq!(*output.0.lock().unwrap(), original);
+    }
+
+    #[test]
+    fn skips_compressed_formats() {
+        let zstd = Some(Compression::Zstd);
+        assert_eq!(choose(zstd, "archive.zip"), None);
+        assert_eq!(choose(zstd, "photo.jpeg"), None);
+        assert_eq!(choose(zstd, "photo.JPG"), None);
+        assert_eq!(choose(zstd, "notes.txt"), zstd);
+        assert_eq!(choose(zstd, "no-extension"), zstd);
+        assert_eq!(choose(None, "notes.txt"), None);
+    }
+}
@@ -0,0 +1,387 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{anyhow, bail, Result};
+use crate::identity::{self, Identity};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use bytes::Bytes;
+use ed25519_dalek::VerifyingKey;
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+const NONCE_LEN: usize = 12;
+const SESSION_KEY_INFO: &[u8] = b"instant-pastebin v1 session key";
+const VERIFICATION_CODE_INFO: &[u8] = b"instant-pastebin v1 verification code";
+const CHANNEL_BINDING_INFO: &[u8] = b"instant-pastebin v1 channel binding";
+const SALT_LEN: usize = 16;
+const IDENTITY_PROOF_CONTEXT: &[u8] = b"instant-pastebin v1 identity proof";
+
+/// Ephemeral X25519 key pair, the public half travels in the signalling `publicKey` fields
+pub(crate) struct KeyPair {
+    secret: StaticSecret,
+    pub public: PublicKey,
+}
+
+impl KeyPair {
+    pub fn generate() -> Self {
+        let secret = StaticSecret::from(rand::random::<[u8; 32]>());
+        let public = PublicKey::from(&secret);
+        KeyPair { secret, public }
+    }
+
+    pub fn public_base64(&self) -> String {
+        BASE64.encode(self.public.as_bytes())
+    }
+
+    /// `publicKey` field that also proves we own `identity`, `peer` being the other side's key
+    pub fn public_with_identity(&self, identity: &Identity, peer: &PublicKey) -> String {
+        let signature = identity.sign(&identity_proof_message(&self.public, peer));
+        format!("{}.{}.{}", self.public_base64(), identity.public_base64(), BASE64.encode(signature.to_bytes()))
+    }
+
+    /// Derive the shared session key, `offer` and `answer` are both sides' public keys
+    pub fn session_key(
+        &self,
+        peer: &PublicKey,
+        offer: &PublicKey,
+        answer: &PublicKey,
+        binding: Option<&Binding>,
+    ) -> Result<[u8; 32]> {
+        let shared = self.secret.diffie_hellman(peer);
+        if !shared.was_contributory() {
+            bail!("Peer sent a low order public key");
+        }
+        let salt = [offer.as_bytes().as_slice(), answer.as_bytes().as_slice()].concat();
+        let mut ikm = shared.as_bytes().to_vec();
+        let mut info = SESSION_KEY_INFO.to_vec();
+        if let Some(binding) = binding {
+            ikm.extend_from_slice(&binding.pake_key);
+            for fingerprint in &binding.fingerprints {
+                info.extend_from_slice(&(fingerprint.len() as u32).to_be_bytes());
+                info.extend_from_slice(fingerprint.as_bytes());
+            }
+        }
+        let mut key = [0u8; 32];
+        Hkdf::<Sha256>::new(Some(&salt), &ikm)
+            .expand(&info, &mut key)
+            .map_err(|e| anyhow!("{e}"))?;
+        Ok(key)
+    }
+}
+
+/// Ties the session key to the secret half of the session code and to the
+/// DTLS certificates of this particular connection
+pub(crate) struct Binding {
+    pake_key: [u8; 32],
+    fingerprints: Vec<String>,
+}
+
+impl Binding {
+    pub fn new(pake_key: [u8; 32], offer_sdp: &str, answer_sdp: &str) -> Result<Self> {
+        let mut fingerprints = Vec::new();
+        for sdp in [offer_sdp, answer_sdp] {
+            let found = self::fingerprints(sdp);
+            if found.is_empty() {
+                bail!("Session description has no DTLS fingerprint");
+            }
+            fingerprints.extend(found);
+        }
+        Ok(Binding { pake_key, fingerprints })
+    }
+}
+
+pub(crate) fn parse_public_key(encoded: &str) -> Result<PublicKey> {
+    let bytes: [u8; 32] = BASE64
+        .decode(encoded)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("Peer did not send a usable public key, refusing an unencrypted transfer"))?;
+    Ok(PublicKey::from(bytes))
+}
+
+/// Encrypt a session description for the signalling server.
+///
+/// The key is stretched from the secret half of the session code with
+/// Argon2id, so the server can not cheaply guess its way to the peers' IPs.
+/// Output is `salt || iv || ciphertext` in base64.
+pub(crate) fn seal_description(secret: &str, description: &impl Serialize) -> Result<String> {
+    let salt = rand::random::<[u8; SALT_LEN]>();
+    let session = Session::new(&stretch(secret, &salt)?);
+    let sealed = session.seal(&serde_json::to_vec(description)?);
+    Ok(BASE64.encode([salt.as_slice(), &sealed].concat()))
+}
+
+pub(crate) fn open_description<T: DeserializeOwned>(secret: &str, sealed: &str) -> Result<T> {
+    let data = BASE64.decode(sealed)?;
+    if data.len() < SALT_LEN {
+        bail!("Encrypted session description too short");
+    }
+    let (salt, sealed) = data.split_at(SALT_LEN);
+    let session = Session::new(&stretch(secret, salt)?);
+    let plaintext = session
+        .open(sealed)
+        .map_err(|_| anyhow!("Can not decrypt the session description, check the session code"))?;
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+/// Argon2id, for keys that come from something a human typed
+fn stretch(password: &str, salt: &[u8]) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    argon2::Argon2::default()
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("{e}"))?;
+    Ok(key)
+}
+
+/// Short code both users compare out loud to rule out a man in the middle.
+///
+/// Covers the DTLS fingerprints of both SDPs and both public keys, so a
+/// signalling server that swapped any of them makes the two codes differ.
+pub(crate) fn verification_code(offer_sdp: &str, answer_sdp: &str, offer: &PublicKey, answer: &PublicKey) -> Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(VERIFICATION_CODE_INFO);
+    for sdp in [offer_sdp, answer_sdp] {
+        let fingerprints = fingerprints(sdp);
+        if fingerprints.is_empty() {
+            bail!("Session description has no DTLS fingerprint");
+        }
+        for fingerprint in fingerprints {
+            hasher.update((fingerprint.len() as u32).to_be_bytes());
+            hasher.update(fingerprint.as_bytes());
+        }
+    }
+    hasher.update(offer.as_bytes());
+    hasher.update(answer.as_bytes());
+    let digest = hasher.finalize();
+    let number = u64::from_be_bytes(digest[..8].try_into().unwrap()) % 1_000_000;
+    Ok(format!("{:03} {:03}", number / 1000, number % 1000))
+}
+
+/// `a=fingerprint` values, normalised so both sides hash the same bytes
+fn fingerprints(sdp: &str) -> Vec<String> {
+    sdp.lines()
+        .filter_map(|line| line.trim().strip_prefix("a=fingerprint:"))
+        .map(|value| value.trim().to_ascii_lowercase())
+        .collect()
+}
+
+/// A peer's `publicKey` signalling field: the ephemeral X25519 key, optionally
+/// followed by a long-term identity that signed both sides' ephemeral keys, as
+/// `<key>.<identity>.<signature>`
+pub(crate) struct PeerKey {
+    pub ephemeral: PublicKey,
+    pub identity: Option<VerifyingKey>,
+}
+
+/// Parse a peer's `publicKey` field, checking the identity proof if there is one
+pub(crate) fn parse_peer_key(field: &str, own: &PublicKey) -> Result<PeerKey> {
+    let mut parts = field.split('.');
+    let ephemeral = parse_public_key(parts.next().unwrap_or_default())?;
+    let identity = match (parts.next(), parts.next(), parts.next()) {
+        (None, _, _) => None,
+        (Some(identity), Some(signature), None) => {
+            let identity = identity::parse_key(identity)?;
+            let message = identity_proof_message(&ephemeral, own);
+            identity::verify(&identity, &message, &identity::parse_signature(signature)?)
+                .map_err(|_| anyhow!("Peer failed to prove it owns identity {}", identity::encode_key(&identity)))?;
+            Some(identity)
+        }
+        _ => bail!("Malformed public key field"),
+    };
+    Ok(PeerKey { ephemeral, identity })
+}
+
+// Signing the other side's fresh key keeps the proof from being replayed in another session
+fn identity_proof_message(signer: &PublicKey, peer: &PublicKey) -> Vec<u8> {
+    [IDENTITY_PROOF_CONTEXT, signer.as_bytes(), peer.as_bytes()].concat()
+}
+
+/// Running digest of every message the sender sends before `end`, which its
+/// signature covers. Text and binary messages are told apart and length
+/// prefixed, so no two message sequences hash the same.
+#[derive(Default)]
+pub(crate) struct Transcript(Sha256);
+
+impl Transcript {
+    pub fn record_text(&mut self, data: &[u8]) {
+        self.record(0, data);
+    }
+
+    pub fn record_binary(&mut self, data: &[u8]) {
+        self.record(1, data);
+    }
+
+    fn record(&mut self, kind: u8, data: &[u8]) {
+        self.0.update([kind]);
+        self.0.update((data.len() as u64).to_be_bytes());
+        self.0.update(data);
+    }
+
+    /// Hex digest of everything recorded so far
+    pub fn hash(&self) -> String {
+        hex::encode(self.0.clone().finalize())
+    }
+}
+
+/// Text messages on the wire, same shape as the blocks of `encryptBlocks` in the web client
+#[derive(Serialize, Deserialize)]
+struct SealedText {
+    iv: String,
+    ct: String,
+}
+
+pub(crate) fn new_password_salt() -> String {
+    BASE64.encode(rand::random::<[u8; SALT_LEN]>())
+}
+
+/// AES-256-GCM over every data channel message
+pub(crate) struct Session {
+    cipher: Aes256Gcm,
+    /// Public value unique to the session key, so password proofs can not be replayed elsewhere
+    binding: [u8; 32],
+}
+
+impl Session {
+    pub fn new(key: &[u8; 32]) -> Self {
+        let binding = Sha256::new().chain_update(CHANNEL_BINDING_INFO).chain_update(key).finalize().into();
+        Session { cipher: Aes256Gcm::new(key.into()), binding }
+    }
+
+    /// Show that we know the sender's password without revealing it, in base64
+    pub fn password_proof(&self, password: &str, salt: &str) -> Result<String> {
+        let mac = self.password_mac(password, salt)?;
+        Ok(BASE64.encode(mac.finalize().into_bytes()))
+    }
+
+    pub fn check_password_proof(&self, password: &str, salt: &str, proof: &str) -> Result<bool> {
+        let mac = self.password_mac(password, salt)?;
+        Ok(BASE64.decode(proof).is_ok_and(|proof| mac.verify_slice(&proof).is_ok()))
+    }
+
+    fn password_mac(&self, password: &str, salt: &str) -> Result<Hmac<Sha256>> {
+        let key = stretch(password, &BASE64.decode(salt)?)?;
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&key).expect("HMAC accepts keys of any length");
+        mac.update(&self.binding);
+        Ok(mac)
+    }
+
+    /// Encrypt a binary message as `iv || ciphertext`
+    pub fn seal(&self, plaintext: &[u8]) -> Bytes {
+        let (nonce, ciphertext) = self.encrypt(plaintext);
+        Bytes::from([nonce.as_slice(), &ciphertext].concat())
+    }
+
+    pub fn open(&self, data: &[u8]) -> Result<Bytes> {
+        if data.len() < NONCE_LEN {
+            bail!("Encrypted message too short");
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        Ok(Bytes::from(self.decrypt(nonce, ciphertext)?))
+    }
+
+    /// Encrypt a text message as `{"iv": ..., "ct": ...}`
+    pub fn seal_text(&self, plaintext: &str) -> String {
+        let (nonce, ciphertext) = self.encrypt(plaintext.as_bytes());
+        serde_json::to_string(&SealedText { iv: BASE64.encode(nonce), ct: BASE64.encode(ciphertext) }).unwrap()
+    }
+
+    pub fn open_text(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let sealed: SealedText = serde_json::from_slice(data)?;
+        self.decrypt(&BASE64.decode(sealed.iv)?, &BASE64.decode(sealed.ct)?)
+    }
+
+    fn encrypt(&self, plaintext: &[u8]) -> ([u8; NONCE_LEN], Vec<u8>) {
+        let nonce = rand::random::<[u8; NONCE_LEN]>();
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .expect("AES-GCM encryption can not fail for in-memory buffers");
+        (nonce, ciphertext)
+    }
+
+    fn decrypt(&self, nonce: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        if nonce.len() != NONCE_LEN {
+            bail!("Bad nonce length {}", nonce.len());
+        }
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow!("Message failed authentication"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn both_sides_derive_the_same_key() {
+        let (offer, answer) = (KeyPair::generate(), KeyPair::generate());
+        let sender = offer.session_key(&answer.public, &offer.public, &answer.public, None).unwrap();
+        let receiver = answer.session_key(&offer.public, &offer.public, &answer.public, None).unwrap();
+        assert_eq!(sender, receiver);
+
+        let stranger = KeyPair::generate();
+        let other = stranger.session_key(&offer.public, &offer.public, &stranger.public, None).unwrap();
+        assert_ne!(sender, other);
+    }
+
+    #[test]
+    fn seal_and_open() {
+        let key = rand::random::<[u8; 32]>();
+        let (sender, receiver) = (Session::new(&key), Session::new(&key));
+        assert_eq!(receiver.open(&sender.seal(b"frame")).unwrap().as_ref(), b"frame");
+        assert_eq!(receiver.open_text(sender.seal_text("{}").as_bytes()).unwrap(), b"{}");
+        // Fresh nonces, the same message never looks the same twice
+        assert_ne!(sender.seal(b"frame"), sender.seal(b"frame"));
+    }
+
+    #[test]
+    fn tampering_is_rejected() {
+        let session = Session::new(&rand::random::<[u8; 32]>());
+        let mut sealed = session.seal(b"frame").to_vec();
+        let last = sealed.len() - 1;
+        sealed[last] ^= 1;
+        assert!(session.open(&sealed).is_err());
+        assert!(session.open(&sealed[..NONCE_LEN - 1]).is_err());
+        assert!(Session::new(&rand::random::<[u8; 32]>()).open(&session.seal(b"frame")).is_err());
+    }
+
+    #[test]
+    fn password_proofs() {
+        let key = rand::random::<[u8; 32]>();
+        let (sender, receiver) = (Session::new(&key), Session::new(&key));
+        let salt = new_password_salt();
+        let proof = receiver.password_proof("hunter2", &salt).unwrap();
+        assert!(sender.check_password_proof("hunter2", &salt, &proof).unwrap());
+        assert!(!sender.check_password_proof("hunter3", &salt, &proof).unwrap());
+        assert!(!sender.check_password_proof("hunter2", &new_password_salt(), &proof).unwrap());
+        assert!(!sender.check_password_proof("hunter2", &salt, "not base64!").unwrap());
+        // Bound to the session, a proof overheard elsewhere is useless
+        let other = Session::new(&rand::random::<[u8; 32]>());
+        assert!(!other.check_password_proof("hunter2", &salt, &proof).unwrap());
+    }
+
+    #[test]
+    fn identity_proofs() {
+        let (sender, receiver) = (KeyPair::generate(), KeyPair::generate());
+        let identity = Identity::generate();
+        let field = receiver.public_with_identity(&identity, &sender.public);
+        let peer = parse_peer_key(&field, &sender.public).unwrap();
+        assert_eq!(peer.ephemeral, receiver.public);
+        assert_eq!(peer.identity, Some(identity.public()));
+        assert!(parse_peer_key(&receiver.public_base64(), &sender.public).unwrap().identity.is_none());
+
+        // Claiming someone else's identity with our own signature
+        let impostor = receiver.public_with_identity(&Identity::generate(), &sender.public);
+        let mut parts: Vec<&str> = impostor.split('.').collect();
+        let claimed = identity.public_base64();
+        parts[1] = &claimed;
+        assert!(parse_peer_key(&parts.join("."), &sender.public).is_err());
+        // A proof made for another session
+        assert!(parse_peer_key(&field, &KeyPair::generate().public).is_err());
+    }
+}
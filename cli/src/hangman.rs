@@ -0,0 +1,129 @@
+use crate::challenge::{Difficulty, Outcome, Settings};
+use crate::wordle::WORDS;
+use rand::prelude::IndexedRandom;
+use std::time::Duration;
+
+#[derive(Debug, PartialEq)]
+pub enum Guess {
+    Hit,
+    Miss,
+    Repeated,
+    Win,
+    Loss,
+}
+
+pub struct Hangman {
+    word: &'static str,
+    guessed: Vec<char>,
+    pub lives: u8,
+}
+
+impl Hangman {
+    pub fn new(settings: &Settings) -> Self {
+        let lives = match settings.difficulty {
+            Difficulty::Easy => 10,
+            Difficulty::Normal => 7,
+            Difficulty::Hard => 5,
+        };
+        Hangman::with_word(WORDS.choose(&mut rand::rng()).unwrap(), lives)
+    }
+
+    fn with_word(word: &'static str, lives: u8) -> Self {
+        Hangman { word, guessed: Vec::new(), lives }
+    }
+
+    /// The word with letters not guessed yet blanked out
+    pub fn pattern(&self) -> String {
+        self.word
+            .chars()
+            .map(|letter| if self.guessed.contains(&letter) { letter.to_string() } else { "_".to_owned() })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// One letter, or the whole word at the risk of a life
+    pub fn guess(&mut self, guess: &str) -> Guess {
+        let mut letters = guess.chars();
+        let result = match (letters.next(), letters.next()) {
+            (Some(letter), None) if self.guessed.contains(&letter) => return Guess::Repeated,
+            (Some(letter), None) => {
+                self.guessed.push(letter);
+                if self.word.contains(letter) { Guess::Hit } else { Guess::Miss }
+            }
+            _ if guess == self.word => return Guess::Win,
+            _ => Guess::Miss,
+        };
+        if self.word.chars().all(|letter| self.guessed.contains(&letter)) {
+            return Guess::Win;
+        }
+        if result == Guess::Miss {
+            self.lives -= 1;
+            if self.lives == 0 {
+                return Guess::Loss;
+            }
+        }
+        result
+    }
+
+    pub async fn play(settings: &Settings) -> Outcome {
+        let mut game = Hangman::new(settings);
+        eprintln!("Guess the word letter by letter, or all at once, you have {} lives", game.lives);
+        let timeout = settings.answer_timeout.map(Duration::from_secs);
+        loop {
+            eprintln!("{}", game.pattern());
+            let line = match crate::input::read_line(timeout).await {
+                Ok(Some(line)) => line.trim().to_lowercase(),
+                Ok(None) => return Outcome::Abort,
+                Err(_) => {
+                    eprintln!("Time is up");
+                    return Outcome::Fail;
+                }
+            };
+            if line.is_empty() || !line.chars().all(|letter| letter.is_ascii_lowercase()) {
+                eprintln!("Guess a letter or the word");
+                continue;
+            }
+            match game.guess(&line) {
+                Guess::Win => {
+                    eprintln!("You win! It was {}", game.word);
+                    return Outcome::Pass;
+                }
+                Guess::Loss => {
+                    eprintln!("Hanged, it was {}", game.word);
+                    return Outcome::Fail;
+                }
+                Guess::Hit => {}
+                Guess::Miss => eprintln!("Nope, {} lives left", game.lives),
+                Guess::Repeated => eprintln!("You already tried {line}"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letters_fill_the_pattern() {
+        let mut game = Hangman::with_word("crane", 3);
+        assert_eq!(game.guess("a"), Guess::Hit);
+        assert_eq!(game.guess("a"), Guess::Repeated);
+        assert_eq!(game.guess("z"), Guess::Miss);
+        assert_eq!(game.pattern(), "_ _ a _ _");
+        for letter in ["c", "r", "n"] {
+            assert_eq!(game.guess(letter), Guess::Hit);
+        }
+        assert_eq!(game.guess("e"), Guess::Win);
+    }
+
+    #[test]
+    fn wrong_words_cost_lives() {
+        let mut game = Hangman::with_word("crane", 2);
+        assert_eq!(game.guess("crate"), Guess::Miss);
+        assert_eq!(game.lives, 1);
+        assert_eq!(game.guess("x"), Guess::Loss);
+        let mut game = Hangman::with_word("crane", 2);
+        assert_eq!(game.guess("crane"), Guess::Win);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::common::TransferSignature;
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const IDENTITY_FILE: &str = "identity";
+const ALIASES_FILE: &str = "aliases";
+const KNOWN_SENDERS_FILE: &str = "known_senders";
+const TRANSFER_SIGNATURE_CONTEXT: &[u8] = b"instant-pastebin v1 transfer signature";
+
+/// Where long-term keys and address books live, created on first use
+pub(crate) fn config_dir() -> Result<PathBuf> {
+    let dir = dirs::config_dir()
+        .ok_or_else(|| anyhow!("Can not find a config directory for this user"))?
+        .join("instant-pastebin");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Long-term ed25519 key pair that other machines can recognise us by
+pub(crate) struct Identity {
+    key: SigningKey,
+}
+
+impl Identity {
+    /// Read the identity from the config directory, generating one the first time
+    pub fn load_or_create() -> Result<Self> {
+        let path = config_dir()?.join(IDENTITY_FILE);
+        match fs::read_to_string(&path) {
+            Ok(data) => {
+                let seed: [u8; 32] = BASE64
+                    .decode(data.trim())
+                    .ok()
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or_else(|| anyhow!("Identity file {} is corrupted", path.display()))?;
+                return Ok(Identity { key: SigningKey::from_bytes(&seed) });
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).with_context(|| format!("Can not read identity from {}", path.display())),
+        }
+
+        let identity = Identity::generate();
+        let mut file = match create_private(&path) {
+            Ok(file) => file,
+            // Another instance got there first, use its identity
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => return Self::load_or_create(),
+            Err(e) => return Err(e).with_context(|| format!("Can not save identity to {}", path.display())),
+        };
+        file.write_all(BASE64.encode(identity.key.to_bytes()).as_bytes())
+            .with_context(|| format!("Can not save identity to {}", path.display()))?;
+        Ok(identity)
+    }
+
+    pub fn generate() -> Self {
+        Identity { key: SigningKey::from_bytes(&rand::random()) }
+    }
+
+    pub fn public(&self) -> VerifyingKey {
+        self.key.verifying_key()
+    }
+
+    pub fn public_base64(&self) -> String {
+        encode_key(&self.public())
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        self.key.sign(message)
+    }
+
+    /// Vouch for a transfer whose transcript hashes to `hash`
+    pub fn sign_transfer(&self, hash: String) -> TransferSignature {
+        let signature = self.sign(&[TRANSFER_SIGNATURE_CONTEXT, hash.as_bytes()].concat());
+        TransferSignature {
+            sender: self.public_base64(),
+            hash,
+            signature: BASE64.encode(signature.to_bytes()),
+        }
+    }
+}
+
+/// A new file only we can read, from the moment it exists
+#[cfg(unix)]
+fn create_private(path: &Path) -> std::io::Result<File> {
+    use std::os::unix::fs::OpenOptionsExt;
+    OpenOptions::new().write(true).create_new(true).mode(0o600).open(path)
+}
+
+#[cfg(not(unix))]
+fn create_private(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().write(true).create_new(true).open(path)
+}
+
+/// Check a transfer signature against what actually arrived, returning the signer
+pub(crate) fn verify_transfer(signature: &TransferSignature, hash: &str) -> Result<VerifyingKey> {
+    if signature.hash != hash {
+        bail!("Sender signed different data than what arrived");
+    }
+    let sender = parse_key(&signature.sender)?;
+    let message = [TRANSFER_SIGNATURE_CONTEXT, hash.as_bytes()].concat();
+    verify(&sender, &message, &parse_signature(&signature.signature)?)
+        .map_err(|_| anyhow!("Signature does not match sender {}", signature.sender))?;
+    Ok(sender)
+}
+
+pub(crate) fn encode_key(key: &VerifyingKey) -> String {
+    BASE64.encode(key.as_bytes())
+}
+
+pub(crate) fn parse_key(encoded: &str) -> Result<VerifyingKey> {
+    let bytes: [u8; 32] = BASE64
+        .decode(encoded)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("{encoded:?} is not an identity key"))?;
+    Ok(VerifyingKey::from_bytes(&bytes)?)
+}
+
+pub(crate) fn parse_signature(encoded: &str) -> Result<Signature> {
+    let bytes: [u8; 64] = BASE64
+        .decode(encoded)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("Malformed signature"))?;
+    Ok(Signature::from_bytes(&bytes))
+}
+
+pub(crate) fn verify(key: &VerifyingKey, message: &[u8], signature: &Signature) -> Result<()> {
+    key.verify(message, signature).map_err(|_| anyhow!("Bad signature"))
+}
+
+/// Names for known keys, one `<alias> <key>` per line of the aliases file
+pub(crate) fn aliases() -> Result<Vec<(String, VerifyingKey)>> {
+    read_keys(ALIASES_FILE)
+}
+
+fn read_keys(file: &str) -> Result<Vec<(String, VerifyingKey)>> {
+    let path = config_dir()?.join(file);
+    let Ok(data) = fs::read_to_string(&path) else {
+        return Ok(Vec::new());
+    };
+    let mut keys = Vec::new();
+    for line in data.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((alias, key)) = line.split_once(char::is_whitespace) else {
+            bail!("Malformed line in {}: {line:?}", path.display());
+        };
+        keys.push((alias.to_owned(), parse_key(key.trim())?));
+    }
+    Ok(keys)
+}
+
+/// Resolve `--to` values, each either a key or an alias
+pub(crate) fn resolve(values: &[String]) -> Result<Vec<VerifyingKey>> {
+    let aliases = aliases()?;
+    values
+        .iter()
+        .map(|value| match aliases.iter().find(|(alias, _)| alias == value) {
+            Some((_, key)) => Ok(*key),
+            None => parse_key(value).with_context(|| format!("{value:?} is neither a key nor a known alias")),
+        })
+        .collect()
+}
+
+/// Alias of `key` if it has one, the key itself otherwise
+pub(crate) fn describe(key: &VerifyingKey) -> String {
+    aliases()
+        .ok()
+        .and_then(|aliases| aliases.into_iter().find(|(_, known)| known == key))
+        .map(|(alias, _)| alias)
+        .unwrap_or_else(|| encode_key(key))
+}
+
+/// Name of a sender we have received from before, same format as the aliases file
+pub(crate) fn known_sender(key: &VerifyingKey) -> Result<Option<String>> {
+    Ok(read_keys(KNOWN_SENDERS_FILE)?
+        .into_iter()
+        .find(|(_, known)| known == key)
+        .map(|(name, _)| name))
+}
+
+/// Trust a new sender from now on, returning the name it was saved under
+pub(crate) fn remember_sender(key: &VerifyingKey) -> Result<String> {
+    let name = format!("sender-{}", hex::encode(&key.as_bytes()[..4]));
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(config_dir()?.join(KNOWN_SENDERS_FILE))?;
+    writeln!(file, "{name} {}", encode_key(key))?;
+    Ok(name)
+}
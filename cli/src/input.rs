@@ -0,0 +1,36 @@
+use std::io::BufRead;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, Receiver};
+use tokio::sync::Mutex;
+use tokio::time::error::Elapsed;
+
+static LINES: OnceLock<Mutex<Receiver<String>>> = OnceLock::new();
+
+/// Lines typed on stdin, read by a thread of their own.
+///
+/// A plain thread rather than `tokio::io::stdin`, whose pending read would
+/// hold up runtime shutdown until the user presses enter.
+fn lines() -> &'static Mutex<Receiver<String>> {
+    LINES.get_or_init(|| {
+        let (tx, rx) = mpsc::channel(16);
+        std::thread::spawn(move || {
+            for line in std::io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                if tx.blocking_send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Mutex::new(rx)
+    })
+}
+
+/// Next line the user typed, none once stdin is closed. Gives up after `timeout`, if any
+pub(crate) async fn read_line(timeout: Option<Duration>) -> Result<Option<String>, Elapsed> {
+    let mut lines = lines().lock().await;
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, lines.recv()).await,
+        None => Ok(lines.recv().await),
+    }
+}
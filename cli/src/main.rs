@@ -1,14 +1,34 @@
-use clap::{AppSettings, Arg, ArgAction, Command};
+use clap::{value_parser, AppSettings, Arg, ArgAction, Command};
 mod server;
+mod challenge;
 mod client;
 mod signalling;
+mod tui;
 mod wordle;
+mod hangman;
+mod number_guess;
+mod sudoku;
+mod typing_test;
 mod common;
+mod compression;
+mod crypto;
+mod identity;
+mod input;
+mod manifest;
+mod pacing;
+mod pake;
+mod resume;
+#[cfg(feature = "solver")]
+mod solver;
+mod stats;
 
 use std::io::Write;
+use std::path::PathBuf;
 
 #[tokio::main]
 async fn main() {
+    let challenges = challenge::Registry::builtin();
+    let challenge_help = format!("Challenge to pass while sending, one of:\n{}", challenges.describe());
     let mut app = Command::new("instant-pastebin")
         .setting(AppSettings::DeriveDisplayOrder)
         .subcommand_negates_reqs(true)
@@ -28,15 +48,222 @@ async fn main() {
                 .long("send")
                 .conflicts_with("receive")
                 .action(ArgAction::Set)
-                .help("Initiate channel to send data")
+                .multiple_values(true)
+                .value_name("PATH")
+                .help("Initiate channel to send data, files and directories or - for stdin")
+        )
+        .arg(
+            Arg::new("resume")
+                .long("resume")
+                .requires("send")
+                .action(ArgAction::Set)
+                .value_name("TRANSFER-ID")
+                .help("Continue an interrupted transfer with the given ID")
+        )
+        .arg(
+            Arg::new("compress")
+                .long("compress")
+                .requires("send")
+                .action(ArgAction::Set)
+                .value_name("ALGORITHM")
+                .value_parser(["zstd"])
+                .min_values(0)
+                .default_missing_value("zstd")
+                .help("Compress data on the fly, unless it is compressed already")
+        )
+        .arg(
+            Arg::new("confirm")
+                .long("confirm")
+                .requires("send")
+                .help("Wait until you confirm that the receiver shows the same verification code")
+        )
+        .arg(
+            Arg::new("password")
+                .long("password")
+                .action(ArgAction::Set)
+                .value_name("PASSWORD")
+                .min_values(0)
+                .help("Sender: require the receiver to know a password, asked for if not given. Receiver: the password to answer with")
+        )
+        .arg(
+            Arg::new("to")
+                .long("to")
+                .requires("send")
+                .action(ArgAction::Append)
+                .value_name("KEY|ALIAS")
+                .help("Only send to a receiver with this identity, may be given several times")
+        )
+        .arg(
+            Arg::new("web")
+                .long("web")
+                .requires("send")
+                .conflicts_with_all(&["to", "password", "compress"])
+                .help("Let a browser receive a single file. The code then has no secret part and the signalling server can read the session description")
+        )
+        .arg(
+            Arg::new("identity")
+                .long("identity")
+                .help("Print this machine's identity key, for the sender's --to")
+        )
+        .arg(
+            Arg::new("challenge")
+                .long("challenge")
+                .requires("send")
+                .action(ArgAction::Set)
+                .value_name("NAME")
+                .value_parser(clap::builder::PossibleValuesParser::new(challenges.names()))
+                .default_value(challenge::DEFAULT_CHALLENGE)
+                .help("Anti-robot challenge to pass while sending")
+                .long_help(challenge_help.as_str())
+        )
+        .arg(
+            Arg::new("no-challenges")
+                .long("no-challenges")
+                .requires("send")
+                .conflicts_with_all(&["challenge-interval", "challenge-side", "difficulty", "hard-mode", "answer-timeout"])
+                .help("Send without any challenges, the receiver is told that none were enforced")
+        )
+        .arg(
+            Arg::new("challenge-interval")
+                .long("challenge-interval")
+                .requires("send")
+                .action(ArgAction::Set)
+                .value_name("BYTES")
+                .value_parser(pacing::parse_interval)
+                .help("Send this many bytes between challenges [default: 10 KiB]")
+        )
+        .arg(
+            Arg::new("challenge-side")
+                .long("challenge-side")
+                .requires("send")
+                .action(ArgAction::Set)
+                .value_name("SIDE")
+                .value_parser(["sender", "receiver", "both"])
+                .default_value("sender")
+                .help("Who has to pass challenges, the receiver pauses the transfer while it solves one")
+        )
+        .arg(
+            Arg::new("difficulty")
+                .long("difficulty")
+                .requires("send")
+                .action(ArgAction::Set)
+                .value_name("LEVEL")
+                .value_parser(challenge::Difficulty::NAMES)
+                .default_value("normal")
+                .help("How hard challenges are, for the receiver's challenges too: easy gives Wordle 12 attempts at everyday words, normal 8, hard 6 attempts at the full word list")
+        )
+        .arg(
+            Arg::new("hard-mode")
+                .long("hard-mode")
+                .requires("send")
+                .help("Revealed hints must be used in later guesses, for the receiver's challenges too")
+        )
+        .arg(
+            Arg::new("wordle-lang")
+                .long("wordle-lang")
+                .action(ArgAction::Set)
+                .value_name("LANG|FILE")
+                .default_value("en")
+                .help("Words to play Wordle with: en, ru or a file with one five-letter word per line")
+        )
+        .arg(
+            Arg::new("answer-timeout")
+                .long("answer-timeout")
+                .requires("send")
+                .action(ArgAction::Set)
+                .value_name("SECONDS")
+                .value_parser(value_parser!(u64))
+                .help("Fail a challenge when an answer takes longer than this, 0 for no limit, for the receiver's challenges too [default: 120]")
+        )
+        .arg(
+            Arg::new("interval-distribution")
+                .long("interval-distribution")
+                .requires("send")
+                .action(ArgAction::Set)
+                .value_name("DISTRIBUTION")
+                .value_parser(pacing::Distribution::NAMES)
+                .default_value("fixed")
+                .help("Draw the bytes between challenges at random, --challenge-interval being the mean")
+        )
+        .arg(
+            Arg::new("jitter")
+                .long("jitter")
+                .requires("send")
+                .action(ArgAction::Set)
+                .value_name("MILLISECONDS")
+                .value_parser(value_parser!(u64))
+                .help("Wait a random time up to this long before each chunk")
+        )
+        .arg(
+            Arg::new("chunk-size")
+                .long("chunk-size")
+                .requires("send")
+                .action(ArgAction::Set)
+                .value_name("SIZE|MIN-MAX")
+                .value_parser(pacing::parse_chunk_sizes)
+                .help("Bytes per chunk, drawn at random for a range [default: 16 KiB]")
+        )
+        .arg(
+            Arg::new("seed")
+                .long("seed")
+                .requires("send")
+                .action(ArgAction::Set)
+                .value_name("NUMBER")
+                .value_parser(value_parser!(u64))
+                .help("Seed the random intervals, jitter and chunk sizes to replay a transfer pattern")
+        )
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .short('o')
+                .requires("receive")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(PathBuf))
+                .help("Write received data to a file instead of stdout, allows resuming. Directory for multi-file transfers")
+        )
+        .arg(
+            Arg::new("buffer-high")
+                .long("buffer-high")
+                .requires("send")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(usize))
+                .help("Pause sending while more than this many bytes are buffered [default: 1 MiB]")
+        )
+        .arg(
+            Arg::new("buffer-low")
+                .long("buffer-low")
+                .requires("send")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(usize))
+                .help("Resume sending once the buffer drains below this many bytes [default: 256 KiB]")
         )
         .arg(
             Arg::new("receive")
                 .long("receive")
                 .conflicts_with("send")
                 .action(ArgAction::Set)
-                .help("Receive data from channel")
-        );
+                .value_name("SESSION-CODE")
+                .value_parser(value_parser!(signalling::SessionCode))
+                .help("Receive data from channel, the code is printed by the sender")
+        )
+        .subcommand(Command::new("stats").about("Print your Wordle statistics: games played, win rate, streaks and guess distribution"));
+
+    #[cfg(feature = "solver")]
+    {
+        app = app
+            .arg(
+                Arg::new("solve-challenges")
+                    .long("solve-challenges")
+                    .help("Let the built-in solver answer Wordle challenges, for automated tests"),
+            )
+            .arg(
+                Arg::new("wordle-answer")
+                    .long("wordle-answer")
+                    .action(ArgAction::Set)
+                    .value_name("WORD")
+                    .help("Answer of every Wordle challenge, for reproducible tests"),
+            );
+    }
 
     let matches = app.clone().get_matches();
 
@@ -65,12 +292,110 @@ async fn main() {
         env_logger::init();
     }
 
-    if matches.is_present("send") {
-        let filename = matches.get_one::<String>("send").unwrap().clone();
-        server::main(&filename).await.unwrap()
+    let wordle_lang = matches.get_one::<String>("wordle-lang").unwrap();
+    match wordle::Dictionary::load(wordle_lang) {
+        Ok(dictionary) => wordle::use_dictionary(dictionary),
+        Err(e) => {
+            println!("{e:#}");
+            std::process::exit(1);
+        }
+    }
+
+    #[cfg(feature = "solver")]
+    {
+        if matches.is_present("solve-challenges") {
+            solver::enable();
+        }
+        if let Some(word) = matches.get_one::<String>("wordle-answer")
+            && let Err(e) = solver::fix_answer(word)
+        {
+            println!("{e:#}");
+            std::process::exit(1);
+        }
+    }
+
+    if matches.subcommand_matches("stats").is_some() {
+        match stats::Stats::load() {
+            Ok(stats) => println!("{stats}"),
+            Err(e) => {
+                println!("{e:#}");
+                std::process::exit(1);
+            }
+        }
+    } else if matches.is_present("identity") {
+        println!("{}", identity::Identity::load_or_create().unwrap().public_base64());
+    } else if matches.is_present("send") {
+        let paths: Vec<String> = matches.get_many::<String>("send").unwrap().cloned().collect();
+        let mut options = server::Options::default();
+        if let Some(&high) = matches.get_one::<usize>("buffer-high") {
+            options.buffer_high = high;
+        }
+        if let Some(&low) = matches.get_one::<usize>("buffer-low") {
+            options.buffer_low = low;
+        }
+        options.transfer_id = matches.get_one::<String>("resume").cloned();
+        if let Some("zstd") = matches.get_one::<String>("compress").map(String::as_str) {
+            options.compression = Some(common::Compression::Zstd);
+        }
+        options.confirm = matches.is_present("confirm");
+        options.web = matches.is_present("web");
+        options.challenges = !matches.is_present("no-challenges");
+        options.challenge = matches.get_one::<String>("challenge").unwrap().clone();
+        options.challenge_side = match matches.get_one::<String>("challenge-side").map(String::as_str) {
+            Some("receiver") => challenge::Side::Receiver,
+            Some("both") => challenge::Side::Both,
+            _ => challenge::Side::Sender,
+        };
+        let difficulty = matches.get_one::<String>("difficulty").unwrap();
+        options.challenge_settings = challenge::Settings {
+            difficulty: challenge::Difficulty::from_name(difficulty).unwrap(),
+            hard_mode: matches.is_present("hard-mode"),
+            answer_timeout: match matches.get_one::<u64>("answer-timeout") {
+                Some(0) => None,
+                Some(&seconds) => Some(seconds),
+                None => Some(challenge::DEFAULT_ANSWER_TIMEOUT),
+            },
+        };
+        if let Some(&interval) = matches.get_one::<usize>("challenge-interval") {
+            options.challenge_interval = interval;
+        }
+        let distribution = matches.get_one::<String>("interval-distribution").unwrap();
+        options.interval_distribution = pacing::Distribution::from_name(distribution).unwrap();
+        if let Some(&jitter) = matches.get_one::<u64>("jitter") {
+            options.jitter = std::time::Duration::from_millis(jitter);
+        }
+        if let Some(chunk_sizes) = matches.get_one::<std::ops::RangeInclusive<usize>>("chunk-size") {
+            options.chunk_sizes = chunk_sizes.clone();
+        }
+        options.seed = matches.get_one::<u64>("seed").copied();
+        if let Some(recipients) = matches.get_many::<String>("to") {
+            let recipients: Vec<String> = recipients.cloned().collect();
+            options.recipients = identity::resolve(&recipients).unwrap();
+        }
+        if matches.is_present("password") {
+            let password = match matches.get_one::<String>("password") {
+                Some(password) => password.clone(),
+                None => rpassword::prompt_password("Password for this session: ").unwrap(),
+            };
+            if password.is_empty() {
+                println!("Password must not be empty");
+                std::process::exit(1);
+            }
+            options.password = Some(password);
+        }
+        if options.buffer_low > options.buffer_high {
+            println!("--buffer-low must not exceed --buffer-high");
+            std::process::exit(1);
+        }
+        server::main(&paths, options).await.unwrap()
     } else if matches.is_present("receive") {
-        let session_id = matches.get_one::<String>("receive").unwrap().clone();
-        client::main(&session_id).await.unwrap()
+        let code = matches.get_one::<signalling::SessionCode>("receive").unwrap();
+        let output = matches.get_one::<PathBuf>("output").cloned();
+        let password = matches.get_one::<String>("password").cloned();
+        if let Err(e) = client::main(code, output, password).await {
+            println!("{e:#}");
+            std::process::exit(1);
+        }
     } else {
         println!("One of the modes is required");
     }
@@ -0,0 +1,129 @@
+use crate::common::{EntryKind, ManifestEntry};
+use anyhow::{anyhow, bail, Result};
+use log::warn;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_FILE_MODE: u32 = 0o644;
+const DEFAULT_DIR_MODE: u32 = 0o755;
+
+/// A manifest entry together with where it lives on the sender's disk
+pub(crate) struct Source {
+    pub path: PathBuf,
+    pub entry: ManifestEntry,
+}
+
+/// Expand files and directory trees into a flat list, directories before their contents
+pub(crate) fn collect(paths: &[PathBuf]) -> Result<Vec<Source>> {
+    let mut sources = Vec::new();
+    for path in paths {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| anyhow!("Can not send {}: no usable file name", path.display()))?;
+        walk(path, name.to_owned(), &mut sources)?;
+    }
+    Ok(sources)
+}
+
+fn walk(path: &Path, relative: String, sources: &mut Vec<Source>) -> Result<()> {
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.file_type().is_symlink() {
+        warn!("Skipping symlink {}", path.display());
+        return Ok(());
+    }
+    if metadata.is_dir() {
+        sources.push(Source {
+            path: path.to_owned(),
+            entry: ManifestEntry {
+                path: relative.clone(),
+                kind: EntryKind::Directory,
+                size: 0,
+                mode: mode(&metadata, DEFAULT_DIR_MODE),
+            },
+        });
+        let mut children = fs::read_dir(path)?.collect::<Result<Vec<_>, _>>()?;
+        children.sort_by_key(|child| child.file_name());
+        for child in children {
+            let Some(name) = child.file_name().to_str().map(str::to_owned) else {
+                warn!("Skipping {}: file name is not valid UTF-8", child.path().display());
+                continue;
+            };
+            walk(&child.path(), format!("{relative}/{name}"), sources)?;
+        }
+    } else if metadata.is_file() {
+        sources.push(Source {
+            path: path.to_owned(),
+            entry: ManifestEntry {
+                path: relative,
+                kind: EntryKind::File,
+                size: metadata.len(),
+                mode: mode(&metadata, DEFAULT_FILE_MODE),
+            },
+        });
+    } else {
+        warn!("Skipping {}: not a regular file or directory", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn mode(metadata: &fs::Metadata, _default: u32) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o777
+}
+
+#[cfg(not(unix))]
+fn mode(_metadata: &fs::Metadata, default: u32) -> u32 {
+    default
+}
+
+#[cfg(unix)]
+pub(crate) fn set_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn set_mode(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+/// Resolve a manifest path under `root`, refusing anything that could escape it
+pub(crate) fn safe_join(root: &Path, relative: &str) -> Result<PathBuf> {
+    if relative.is_empty() || relative.starts_with('/') || relative.contains('\\') {
+        bail!("Refusing unsafe path {relative:?}");
+    }
+    let mut path = root.to_owned();
+    for part in relative.split('/') {
+        if part.is_empty() || part == "." || part == ".." || part.contains(':') {
+            bail!("Refusing unsafe path {relative:?}");
+        }
+        path.push(part);
+        // An existing symlink anywhere along the way could point outside of root
+        if fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_symlink()) {
+            bail!("Refusing to write through symlink {}", path.display());
+        }
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safe_join_accepts_nested_paths() {
+        let root = Path::new("out");
+        assert_eq!(safe_join(root, "dir/sub/file.txt").unwrap(), root.join("dir").join("sub").join("file.txt"));
+    }
+
+    #[test]
+    fn safe_join_rejects_traversal() {
+        let root = Path::new("out");
+        for bad in ["", "/etc/passwd", "../x", "a/../../x", "a/./b", "a//b", "a\\..\\b", "C:/x", "a/"] {
+            assert!(safe_join(root, bad).is_err(), "{bad:?} should be rejected");
+        }
+    }
+}
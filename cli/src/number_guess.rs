@@ -0,0 +1,152 @@
+use crate::challenge::{Difficulty, Outcome, Settings};
+use rand::Rng;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+#[derive(Debug, PartialEq)]
+pub enum Answer {
+    Higher,
+    Lower,
+    Correct,
+    OutOfRange,
+    /// Hard mode only, the guess ignores earlier hints
+    MissedHint { reason: String },
+}
+
+pub struct NumberGuess {
+    secret: u32,
+    range: RangeInclusive<u32>,
+    pub attempts_left: u8,
+    hard_mode: bool,
+    /// Where the secret can still be according to the hints so far
+    low: u32,
+    high: u32,
+}
+
+impl NumberGuess {
+    /// Harder games widen the range without allowing more than a binary search needs
+    pub fn new(settings: &Settings) -> Self {
+        let (range, attempts_left) = match settings.difficulty {
+            Difficulty::Easy => (1..=100, 10),
+            Difficulty::Normal => (1..=100, 7),
+            Difficulty::Hard => (1..=1000, 10),
+        };
+        NumberGuess {
+            secret: rand::rng().random_range(range.clone()),
+            low: *range.start(),
+            high: *range.end(),
+            range,
+            attempts_left,
+            hard_mode: settings.hard_mode,
+        }
+    }
+
+    /// Numbers out of range or, in hard mode, out of the hinted range do not cost an attempt
+    pub fn guess(&mut self, guess: u32) -> Answer {
+        if !self.range.contains(&guess) {
+            return Answer::OutOfRange;
+        }
+        if self.hard_mode && !(self.low..=self.high).contains(&guess) {
+            return Answer::MissedHint { reason: format!("The number is between {} and {}", self.low, self.high) };
+        }
+        self.attempts_left -= 1;
+        if guess == self.secret {
+            Answer::Correct
+        } else if guess < self.secret {
+            self.low = self.low.max(guess + 1);
+            Answer::Higher
+        } else {
+            self.high = self.high.min(guess - 1);
+            Answer::Lower
+        }
+    }
+
+    pub async fn play(settings: &Settings) -> Outcome {
+        let mut game = NumberGuess::new(settings);
+        eprintln!(
+            "I am thinking of a number from {} to {}, you have {} attempts",
+            game.range.start(),
+            game.range.end(),
+            game.attempts_left
+        );
+        if game.hard_mode {
+            eprintln!("Hard mode: every guess must fit the hints so far");
+        }
+        let timeout = settings.answer_timeout.map(Duration::from_secs);
+        loop {
+            let line = match crate::input::read_line(timeout).await {
+                Ok(Some(line)) => line,
+                Ok(None) => return Outcome::Abort,
+                Err(_) => {
+                    eprintln!("Time is up");
+                    return Outcome::Fail;
+                }
+            };
+            let Ok(number) = line.trim().parse() else {
+                eprintln!("Guess a number");
+                continue;
+            };
+            match game.guess(number) {
+                Answer::Correct => {
+                    eprintln!("You win!");
+                    return Outcome::Pass;
+                }
+                Answer::OutOfRange => eprintln!("Guess a number from {} to {}", game.range.start(), game.range.end()),
+                Answer::MissedHint { reason } => eprintln!("{reason}"),
+                _ if game.attempts_left == 0 => {
+                    eprintln!("No guesses left, it was {}", game.secret);
+                    return Outcome::Fail;
+                }
+                Answer::Higher => eprintln!("Higher, {} more guesses", game.attempts_left),
+                Answer::Lower => eprintln!("Lower, {} more guesses", game.attempts_left),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(difficulty: Difficulty, hard_mode: bool) -> NumberGuess {
+        NumberGuess::new(&Settings { difficulty, hard_mode, ..Settings::default() })
+    }
+
+    #[test]
+    fn limits_per_difficulty() {
+        for (difficulty, range, attempts) in
+            [(Difficulty::Easy, 1..=100, 10), (Difficulty::Normal, 1..=100, 7), (Difficulty::Hard, 1..=1000, 10)]
+        {
+            let game = game(difficulty, false);
+            assert!(range.contains(&game.secret));
+            assert_eq!(game.range, range);
+            assert_eq!(game.attempts_left, attempts);
+        }
+    }
+
+    #[test]
+    fn higher_and_lower() {
+        let mut game = game(Difficulty::Normal, false);
+        game.secret = 42;
+        assert_eq!(game.guess(0), Answer::OutOfRange);
+        assert_eq!(game.guess(101), Answer::OutOfRange);
+        assert_eq!(game.attempts_left, 7);
+        assert_eq!(game.guess(10), Answer::Higher);
+        assert_eq!(game.guess(90), Answer::Lower);
+        // Outside the hints is just a wasted guess without hard mode
+        assert_eq!(game.guess(5), Answer::Higher);
+        assert_eq!(game.guess(42), Answer::Correct);
+        assert_eq!(game.attempts_left, 3);
+    }
+
+    #[test]
+    fn hard_mode_keeps_to_the_hints() {
+        let mut game = game(Difficulty::Normal, true);
+        game.secret = 42;
+        assert_eq!(game.guess(50), Answer::Lower);
+        assert_eq!(game.guess(60), Answer::MissedHint { reason: "The number is between 1 and 49".to_owned() });
+        assert_eq!(game.guess(30), Answer::Higher);
+        assert!(matches!(game.guess(30), Answer::MissedHint { .. }));
+        assert_eq!(game.attempts_left, 5);
+    }
+}
@@ -0,0 +1,189 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution as _, Exp, Poisson};
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+// Leaves room for the frame header and encryption overhead under the usual 64 KiB message limit
+pub(crate) const MAX_CHUNK_SIZE: usize = 60 * 1024;
+// 1 TiB, far beyond any transfer, and keeps every distribution clear of overflow.
+// Also applies to intervals the sender asks a receiver for
+pub(crate) const MAX_INTERVAL: u64 = 1 << 40;
+
+/// How the number of bytes between two challenges is drawn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Distribution {
+    /// Always exactly the mean, the old behaviour
+    #[default]
+    Fixed,
+    /// Anywhere between 1 and twice the mean
+    Uniform,
+    Exponential,
+    Poisson,
+}
+
+impl Distribution {
+    pub const NAMES: [&'static str; 4] = ["fixed", "uniform", "exponential", "poisson"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "fixed" => Some(Distribution::Fixed),
+            "uniform" => Some(Distribution::Uniform),
+            "exponential" => Some(Distribution::Exponential),
+            "poisson" => Some(Distribution::Poisson),
+            _ => None,
+        }
+    }
+}
+
+/// Bytes between challenges, `mean` on average
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Interval {
+    pub distribution: Distribution,
+    pub mean: u64,
+}
+
+impl Interval {
+    /// Never zero, a challenge is always preceded by some data
+    pub fn sample(&self, rng: &mut impl Rng) -> u64 {
+        let mean = self.mean.clamp(1, MAX_INTERVAL);
+        let bytes = match self.distribution {
+            Distribution::Fixed => mean,
+            Distribution::Uniform => rng.random_range(1..=mean.saturating_mul(2).saturating_sub(1).max(1)),
+            Distribution::Exponential => Exp::new(1.0 / mean as f64).unwrap().sample(rng).round() as u64,
+            Distribution::Poisson => Poisson::new(mean as f64).unwrap().sample(rng) as u64,
+        };
+        bytes.max(1)
+    }
+}
+
+/// Parse the mean of `--challenge-interval`
+pub(crate) fn parse_interval(value: &str) -> Result<usize, String> {
+    let interval = value.trim().parse::<u64>().map_err(|e| format!("{value:?}: {e}"))?;
+    if interval == 0 {
+        return Err("The interval must be positive".to_owned());
+    }
+    if interval > MAX_INTERVAL {
+        return Err(format!("The interval can be at most {MAX_INTERVAL} bytes"));
+    }
+    usize::try_from(interval).map_err(|e| e.to_string())
+}
+
+/// Parse `SIZE` or `MIN-MAX` for `--chunk-size`
+pub(crate) fn parse_chunk_sizes(value: &str) -> Result<RangeInclusive<usize>, String> {
+    let parse = |size: &str| size.trim().parse::<usize>().map_err(|e| format!("{size:?}: {e}"));
+    let (min, max) = match value.split_once('-') {
+        Some((min, max)) => (parse(min)?, parse(max)?),
+        None => (parse(value)?, parse(value)?),
+    };
+    if min == 0 || min > max {
+        return Err(format!("{value:?} is not a valid range of chunk sizes"));
+    }
+    if max > MAX_CHUNK_SIZE {
+        return Err(format!("Chunks can be at most {MAX_CHUNK_SIZE} bytes"));
+    }
+    Ok(min..=max)
+}
+
+/// Decides when challenges come up and how data is cut into frames.
+///
+/// Everything is drawn from one generator, so a given seed replays the
+/// exact same transfer pattern.
+pub(crate) struct Pacing {
+    rng: StdRng,
+    interval: Interval,
+    jitter: Duration,
+    chunk_sizes: RangeInclusive<usize>,
+}
+
+impl Pacing {
+    /// `jitter` is the longest pause between frames, fresh randomness unless `seed` is given
+    pub fn new(seed: Option<u64>, interval: Interval, jitter: Duration, chunk_sizes: RangeInclusive<usize>) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_os_rng(),
+        };
+        Pacing { rng, interval, jitter, chunk_sizes }
+    }
+
+    pub fn next_interval(&mut self) -> u64 {
+        self.interval.sample(&mut self.rng)
+    }
+
+    pub fn next_chunk_size(&mut self) -> usize {
+        self.rng.random_range(self.chunk_sizes.clone())
+    }
+
+    pub fn next_delay(&mut self) -> Duration {
+        if self.jitter.is_zero() {
+            return Duration::ZERO;
+        }
+        self.jitter.mul_f64(self.rng.random::<f64>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pacing(seed: u64, distribution: Distribution) -> Pacing {
+        let interval = Interval { distribution, mean: 10_000 };
+        Pacing::new(Some(seed), interval, Duration::from_millis(50), 1024..=4096)
+    }
+
+    fn draw(pacing: &mut Pacing) -> Vec<(u64, usize, Duration)> {
+        (0..32)
+            .map(|_| (pacing.next_interval(), pacing.next_chunk_size(), pacing.next_delay()))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_same_pattern() {
+        for distribution in [Distribution::Uniform, Distribution::Exponential, Distribution::Poisson] {
+            assert_eq!(draw(&mut pacing(7, distribution)), draw(&mut pacing(7, distribution)));
+            assert_ne!(draw(&mut pacing(7, distribution)), draw(&mut pacing(8, distribution)));
+        }
+    }
+
+    #[test]
+    fn samples_stay_in_bounds() {
+        for name in Distribution::NAMES {
+            let mut pacing = pacing(1, Distribution::from_name(name).unwrap());
+            for (interval, chunk, delay) in draw(&mut pacing) {
+                assert!(interval > 0);
+                assert!((1024..=4096).contains(&chunk));
+                assert!(delay <= Duration::from_millis(50));
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_is_regular() {
+        let mut pacing = pacing(3, Distribution::Fixed);
+        assert!((0..8).all(|_| pacing.next_interval() == 10_000));
+    }
+
+    #[test]
+    fn chunk_size_ranges() {
+        assert_eq!(parse_chunk_sizes("4096"), Ok(4096..=4096));
+        assert_eq!(parse_chunk_sizes("1024-8192"), Ok(1024..=8192));
+        assert!(parse_chunk_sizes("0").is_err());
+        assert!(parse_chunk_sizes("8192-1024").is_err());
+        assert!(parse_chunk_sizes("1-1000000").is_err());
+    }
+
+    #[test]
+    fn huge_intervals_are_capped() {
+        assert_eq!(parse_interval("10240"), Ok(10240));
+        assert!(parse_interval("0").is_err());
+        assert!(parse_interval(&(MAX_INTERVAL + 1).to_string()).is_err());
+        let mut rng = StdRng::seed_from_u64(5);
+        for name in Distribution::NAMES {
+            let interval = Interval { distribution: Distribution::from_name(name).unwrap(), mean: u64::MAX };
+            // Exponential has no upper bound, this only rules out wrapping around
+            assert!((0..32).all(|_| (1..=64 * MAX_INTERVAL).contains(&interval.sample(&mut rng))), "{name}");
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use anyhow::{anyhow, bail, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+use rand::seq::IndexedRandom;
+use sha2::{Digest, Sha512};
+
+const GENERATOR_DSI: &[u8] = b"instant-pastebin v1 CPace generator";
+const KEY_DSI: &[u8] = b"instant-pastebin v1 CPace key";
+// Also keys the session descriptions at rest, so it has to withstand offline guessing
+const SECRET_WORDS: usize = 3;
+
+/// Secret half of a session code, only ever shared between the two users
+pub(crate) fn new_secret() -> String {
+    let mut rng = rand::rng();
+    (0..SECRET_WORDS)
+        .map(|_| *crate::wordle::WORDS.choose(&mut rng).unwrap())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// One side of a CPace exchange over Ristretto255.
+///
+/// Both peers derive the same generator from the code's secret and the
+/// sender's public key, so only someone who knows the secret can complete
+/// the exchange, and a wrong guess costs an attacker the whole session.
+pub(crate) struct Pake {
+    scalar: Scalar,
+    message: [u8; 32],
+    context: Vec<u8>,
+}
+
+impl Pake {
+    /// `context` is public data unique to the session, `secret` the part of the code
+    pub fn start(secret: &str, context: &[u8]) -> Self {
+        let generator = generator(secret, context);
+        let scalar = Scalar::from_bytes_mod_order_wide(&rand::random::<[u8; 64]>());
+        let message = (generator * scalar).compress().to_bytes();
+        Pake { scalar, message, context: context.to_owned() }
+    }
+
+    /// What goes into the signalling `pake` field
+    pub fn message_base64(&self) -> String {
+        BASE64.encode(self.message)
+    }
+
+    /// Shared key, `offer` and `answer` are both sides' messages in base64
+    pub fn finish(&self, peer: &str, offer: &str, answer: &str) -> Result<[u8; 32]> {
+        let (peer, offer, answer) = (decode(peer)?, decode(offer)?, decode(answer)?);
+        let point = CompressedRistretto(peer)
+            .decompress()
+            .ok_or_else(|| anyhow!("Peer sent an invalid key exchange message"))?;
+        let shared = point * self.scalar;
+        if shared.is_identity() {
+            bail!("Peer sent a degenerate key exchange message");
+        }
+
+        let mut hasher = Sha512::new();
+        for part in [KEY_DSI, &self.context, shared.compress().as_bytes(), &offer, &answer] {
+            update_prefixed(&mut hasher, part);
+        }
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&hasher.finalize()[..32]);
+        Ok(key)
+    }
+}
+
+fn generator(secret: &str, context: &[u8]) -> RistrettoPoint {
+    let mut hasher = Sha512::new();
+    for part in [GENERATOR_DSI, secret.as_bytes(), context] {
+        update_prefixed(&mut hasher, part);
+    }
+    RistrettoPoint::from_uniform_bytes(&hasher.finalize().into())
+}
+
+fn update_prefixed(hasher: &mut Sha512, data: &[u8]) {
+    hasher.update((data.len() as u32).to_be_bytes());
+    hasher.update(data);
+}
+
+fn decode(message: &str) -> Result<[u8; 32]> {
+    BASE64
+        .decode(message)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("Peer sent a malformed key exchange message"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn both_sides_derive_the_same_key() {
+        let context = b"sender public key";
+        let secret = new_secret();
+        assert_eq!(secret.split('-').count(), SECRET_WORDS);
+        let (sender, receiver) = (Pake::start(&secret, context), Pake::start(&secret, context));
+        let (offer, answer) = (sender.message_base64(), receiver.message_base64());
+        assert_eq!(sender.finish(&answer, &offer, &answer).unwrap(), receiver.finish(&offer, &offer, &answer).unwrap());
+    }
+
+    #[test]
+    fn wrong_secret_gives_another_key() {
+        let context = b"sender public key";
+        let sender = Pake::start("crane-slate-audio", context);
+        let receiver = Pake::start("crane-slate-adieu", context);
+        let (offer, answer) = (sender.message_base64(), receiver.message_base64());
+        assert_ne!(sender.finish(&answer, &offer, &answer).unwrap(), receiver.finish(&offer, &offer, &answer).unwrap());
+    }
+
+    #[test]
+    fn rejects_malformed_messages() {
+        let pake = Pake::start("crane-slate-audio", b"context");
+        let own = pake.message_base64();
+        assert!(pake.finish("not base64!", &own, &own).is_err());
+        assert!(pake.finish(&BASE64.encode([0u8; 31]), &own, &own).is_err());
+        // The identity point would make the shared secret known to anyone
+        assert!(pake.finish(&BASE64.encode([0u8; 32]), &own, &own).is_err());
+    }
+}
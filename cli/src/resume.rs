@@ -0,0 +1,70 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+const SIDECAR_SUFFIX: &str = ".ipb-partial";
+
+/// How far a receiver got with a particular transfer.
+///
+/// Stored next to the output file so that an interrupted download can be
+/// continued from a new session, as long as the sender offers the same
+/// transfer ID and content hash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Progress {
+    #[serde(rename = "transferId")]
+    pub transfer_id: String,
+    #[serde(rename = "contentHash")]
+    pub content_hash: String,
+    pub offset: u64,
+}
+
+impl Progress {
+    pub fn sidecar_path(output: &Path) -> PathBuf {
+        let mut name = output.as_os_str().to_owned();
+        name.push(SIDECAR_SUFFIX);
+        PathBuf::from(name)
+    }
+
+    /// Offset to continue from, or 0 if the sidecar belongs to another transfer
+    pub fn resume_offset(output: &Path, transfer_id: &str, content_hash: &str) -> u64 {
+        let Ok(data) = fs::read(Self::sidecar_path(output)) else {
+            return 0;
+        };
+        let Ok(progress) = serde_json::from_slice::<Progress>(&data) else {
+            return 0;
+        };
+        if progress.transfer_id != transfer_id || progress.content_hash != content_hash {
+            return 0;
+        }
+        // Never trust the sidecar beyond what actually made it to disk
+        let on_disk = fs::metadata(output).map(|m| m.len()).unwrap_or(0);
+        progress.offset.min(on_disk)
+    }
+
+    pub fn save(&self, output: &Path) -> Result<()> {
+        fs::write(Self::sidecar_path(output), serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+
+    pub fn remove(output: &Path) -> Result<()> {
+        match fs::remove_file(Self::sidecar_path(output)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Hex SHA-256 of everything `input` yields, leaving it rewound to the start
+pub(crate) fn content_hash<R: Read + Seek>(input: &mut R) -> Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(input, &mut hasher)?;
+    input.seek(SeekFrom::Start(0))?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+pub(crate) fn new_transfer_id() -> String {
+    hex::encode(rand::random::<[u8; 8]>())
+}
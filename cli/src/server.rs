@@ -1,14 +1,26 @@
+use crate::challenge::{self, Outcome, TransferProgress};
 use crate::common::*;
-use crate::signalling::AnswerTo;
-use crate::wordle::Wordle;
-use anyhow::Result;
-use bytes::{Bytes, BytesMut};
+use crate::compression;
+use crate::crypto::{self, Binding, KeyPair, Session, Transcript};
+use crate::identity::{self, Identity};
+use crate::manifest;
+use crate::pacing::{self, Interval, Pacing};
+use crate::pake::{self, Pake};
+use crate::resume;
+use crate::signalling::{AnswerPeerTo, SessionCode};
+use anyhow::{anyhow, bail, Result};
+use bytes::BytesMut;
+use ed25519_dalek::VerifyingKey;
 use captures::capture;
-use log::{error, info, log};
+use log::{error, info, warn};
+use std::fs::File;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::io;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc::Receiver;
+use tokio::sync::Notify;
 use webrtc::api::APIBuilder;
 use webrtc::api::interceptor_registry::register_default_interceptors;
 use webrtc::api::media_engine::MediaEngine;
@@ -16,15 +28,350 @@ use webrtc::ice_transport::ice_server::RTCIceServer;
 use webrtc::interceptor::registry::Registry;
 use webrtc::peer_connection::configuration::RTCConfiguration;
 use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
-use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
-use std::io::Read;
-use webrtc::data_channel::data_channel_init::RTCDataChannelInit;
+use std::io::{Read, Seek, SeekFrom, Write};
+use webrtc::data_channel::data_channel_message::DataChannelMessage;
+use webrtc::data_channel::RTCDataChannel;
 
-const CHUNK_SIZE: usize = 16 * 1024;
-const BYTES_UNTIL_WORDLE: usize = 1024 * 10; // 10 KiB
-// const BYTES_UNTIL_WORDLE: usize = 64; // 64 B
+pub(crate) const CHUNK_SIZE: usize = 16 * 1024;
+
+pub(crate) const DEFAULT_BUFFER_HIGH: usize = 1024 * 1024; // 1 MiB
+pub(crate) const DEFAULT_BUFFER_LOW: usize = 256 * 1024; // 256 KiB
+// How long to wait for the receiver to name a resume offset before starting from scratch
+const RESUME_TIMEOUT: Duration = Duration::from_secs(5);
+// The receiver may have to type the password first
+const PASSWORD_TIMEOUT: Duration = Duration::from_secs(120);
+
+pub(crate) struct Options {
+    /// Stop reading the input once this many bytes are queued in the data channel
+    pub buffer_high: usize,
+    /// Resume reading once the queue drains below this many bytes
+    pub buffer_low: usize,
+    /// Transfer ID of an interrupted transfer to continue
+    pub transfer_id: Option<String>,
+    /// Compress files that are not compressed already
+    pub compression: Option<Compression>,
+    /// Ask the user to compare verification codes before sending anything
+    pub confirm: bool,
+    /// Password the receiver has to prove it knows
+    pub password: Option<String>,
+    /// Only send to receivers owning one of these identities, anyone if empty
+    pub recipients: Vec<VerifyingKey>,
+    /// Off only with `--no-challenges`, which the receiver gets to see
+    pub challenges: bool,
+    /// Name of the challenge to pass every `challenge_interval` bytes
+    pub challenge: String,
+    pub challenge_interval: usize,
+    pub challenge_side: challenge::Side,
+    /// Difficulty of our challenges, and of the receiver's
+    pub challenge_settings: challenge::Settings,
+    /// How intervals are drawn around `challenge_interval`
+    pub interval_distribution: pacing::Distribution,
+    /// Longest random pause between two frames
+    pub jitter: Duration,
+    /// Payload size of each frame is drawn from this range
+    pub chunk_sizes: RangeInclusive<usize>,
+    /// Makes all of the above reproducible
+    pub seed: Option<u64>,
+    /// Let a browser receive: no secret in the session code, so no PAKE and a readable session description
+    pub web: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            buffer_high: DEFAULT_BUFFER_HIGH,
+            buffer_low: DEFAULT_BUFFER_LOW,
+            transfer_id: None,
+            compression: None,
+            confirm: false,
+            password: None,
+            recipients: Vec::new(),
+            challenges: true,
+            challenge: challenge::DEFAULT_CHALLENGE.to_owned(),
+            challenge_interval: challenge::DEFAULT_INTERVAL,
+            challenge_side: challenge::Side::Sender,
+            challenge_settings: challenge::Settings::default(),
+            interval_distribution: pacing::Distribution::Fixed,
+            jitter: Duration::ZERO,
+            chunk_sizes: CHUNK_SIZE..=CHUNK_SIZE,
+            seed: None,
+            web: false,
+        }
+    }
+}
+
+/// What the sender was asked to send
+enum Transfer {
+    /// A single file or stdin, announced with `Begin` so the web client understands it
+    Single {
+        input: clio::Input,
+        name: String,
+        /// Transfer ID and content hash, when the input can be resumed
+        resumable: Option<(String, String)>,
+    },
+    /// Several files or directory trees, announced with a manifest
+    Tree(Vec<manifest::Source>),
+}
+
+/// Frames data and control messages onto the data channel
+struct Sender {
+    channel: Arc<RTCDataChannel>,
+    buffer_high: usize,
+    buffer_low: Arc<Notify>,
+    /// Our own challenge, none if only the receiver is challenged
+    challenge: Option<challenge::Factory>,
+    challenge_settings: challenge::Settings,
+    /// What the receiver is told about challenges in the transfer metadata
+    policy: ChallengePolicy,
+    pacing: Pacing,
+    bytes_until_challenge: usize,
+    /// File data read so far, for the progress bar of challenges
+    sent: Arc<AtomicU64>,
+    total: Option<u64>,
+    /// Control messages flowing back from the receiver
+    control_rx: Receiver<InfoMessage>,
+    compression: Option<Compression>,
+    session: Arc<Session>,
+    identity: Identity,
+    transcript: Transcript,
+}
+
+impl Sender {
+    async fn send_info(&mut self, info: &InfoMessage) -> Result<()> {
+        let text = serde_json::to_string(info)?;
+        self.transcript.record_text(text.as_bytes());
+        self.channel.send_text(self.session.seal_text(&text)).await?;
+        Ok(())
+    }
+
+    /// Hold off while the receiver is busy with its challenge
+    async fn wait_for_receiver(&mut self) -> Result<()> {
+        while let Ok(message) = self.control_rx.try_recv() {
+            if !matches!(message, InfoMessage::ChallengeStarted {}) {
+                warn!("Unexpected control message from receiver");
+                continue;
+            }
+            println!("Receiver is solving a challenge, pausing");
+            loop {
+                match self.control_rx.recv().await {
+                    Some(InfoMessage::ChallengeFinished { outcome: Outcome::Pass }) => break,
+                    Some(InfoMessage::ChallengeFinished { outcome }) => {
+                        bail!("Receiver did not pass its challenge ({outcome:?})")
+                    }
+                    Some(_) => warn!("Unexpected control message from receiver"),
+                    None => bail!("Receiver went away during its challenge"),
+                }
+            }
+            println!("Receiver passed its challenge, resuming");
+        }
+        Ok(())
+    }
+
+    /// Close the transfer, signing everything sent so far
+    async fn send_end(&mut self) -> Result<()> {
+        let signature = self.identity.sign_transfer(self.transcript.hash());
+        self.send_info(&InfoMessage::End { signature: Some(signature) }).await
+    }
+
+    /// Send everything `input` yields as frames numbered from `offset`
+    async fn send_stream(
+        &mut self,
+        input: &mut (impl Read + Send),
+        mut offset: u64,
+        compression: Option<Compression>,
+    ) -> Result<()> {
+        let mut input = compression::reader(compression, compression::Source::new(input, Arc::clone(&self.sent)))?;
+        let mut buffer = BytesMut::new();
+        let mut sequence: u64 = 0;
+        loop {
+            buffer.resize(self.pacing.next_chunk_size(), 0u8);
+            if self.challenge.is_some() && self.bytes_until_challenge < buffer.len() {
+                buffer.truncate(self.bytes_until_challenge);
+            }
+            let n = input.read(buffer.as_mut())?;
+            if n == 0 {
+                info!("File done");
+                return Ok(());
+            }
+            if let Some(challenge) = self.challenge {
+                self.bytes_until_challenge -= n;
+                if self.bytes_until_challenge == 0 {
+                    println!("Suspicious activity detected");
+                    let progress = TransferProgress { done: self.sent.load(Ordering::Relaxed), total: self.total };
+                    let settings = &self.challenge_settings;
+                    match challenge::run(challenge, settings, progress, &self.channel, &self.session, true).await {
+                        Outcome::Pass => println!("Alright, looks like you are human"),
+                        Outcome::Fail => {
+                            println!("Only a true robot is unable to pass the challenge. Terminating transfer");
+                            bail!("Challenge failed");
+                        }
+                        Outcome::Abort => bail!("Challenge aborted"),
+                    }
+                    self.bytes_until_challenge = self.pacing.next_interval() as usize;
+                }
+            }
+            self.wait_for_receiver().await?;
+            buffer.truncate(n);
+            println!("Sending {} bytes", n);
+
+            let delay = self.pacing.next_delay();
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+
+            while self.channel.buffered_amount().await > self.buffer_high {
+                self.buffer_low.notified().await;
+            }
+
+            let frame = Frame { sequence, offset, payload: buffer.clone().freeze() }.encode();
+            self.transcript.record_binary(&frame);
+            self.channel.send(&self.session.seal(&frame)).await?;
+            sequence += 1;
+            offset += n as u64;
+        }
+    }
+}
+
+/// Make the receiver prove it knows the password before anything is sent
+async fn authenticate(sender: &mut Sender, password: &str) -> Result<()> {
+    let salt = crypto::new_password_salt();
+    sender.send_info(&InfoMessage::PasswordRequired { salt: salt.clone() }).await?;
+    println!("Waiting for the receiver to enter the password");
+    let proof = match tokio::time::timeout(PASSWORD_TIMEOUT, sender.control_rx.recv()).await {
+        Ok(Some(InfoMessage::PasswordProof { proof })) => proof,
+        Ok(_) => bail!("Receiver did not answer the password challenge"),
+        Err(_) => bail!("Receiver did not enter the password in time"),
+    };
+    let accepted = sender.session.check_password_proof(password, &salt, &proof)?;
+    sender.send_info(&InfoMessage::PasswordResult { accepted }).await?;
+    if !accepted {
+        bail!("Receiver entered a wrong password");
+    }
+    println!("Receiver entered the correct password");
+    Ok(())
+}
+
+async fn send_single(
+    sender: &mut Sender,
+    mut input: clio::Input,
+    name: String,
+    resumable: Option<(String, String)>,
+) -> Result<()> {
+    let len = input.len().ok_or_else(|| anyhow!("Can only send files of known size"))?;
+    let (transfer_id, content_hash) = resumable.unzip();
+    let compression = compression::choose(sender.compression, &name);
+    sender.send_info(&InfoMessage::Begin {
+        fileinfo: FileInfo { filename: name, filesize: Some(len as usize) },
+        transfer_id,
+        content_hash: content_hash.clone(),
+        compression,
+        challenges: Some(sender.policy.clone()),
+    }).await?;
+
+    let mut offset: u64 = 0;
+    if content_hash.is_some() {
+        match tokio::time::timeout(RESUME_TIMEOUT, sender.control_rx.recv()).await {
+            Ok(Some(InfoMessage::Resume { offset: resume_offset })) if resume_offset <= len => {
+                offset = resume_offset;
+            }
+            Ok(Some(InfoMessage::Resume { offset: resume_offset })) => {
+                error!("Receiver asked to resume at {resume_offset}, past the end of the file");
+            }
+            Ok(_) => error!("Unexpected reply to begin message"),
+            Err(_) => info!("Receiver did not ask to resume, starting from scratch"),
+        }
+        if offset > 0 {
+            println!("Resuming transfer at byte {offset}");
+            input.seek(SeekFrom::Start(offset))?;
+        }
+    }
+    sender.sent.store(offset, Ordering::Relaxed);
+    sender.total = Some(len);
+
+    sender.send_stream(&mut input, offset, compression).await?;
+    sender.send_end().await
+}
+
+async fn send_tree(sender: &mut Sender, sources: Vec<manifest::Source>) -> Result<()> {
+    sender.total = Some(sources.iter().map(|source| source.entry.size).sum());
+    sender.send_info(&InfoMessage::Manifest {
+        entries: sources.iter().map(|source| source.entry.clone()).collect(),
+        challenges: Some(sender.policy.clone()),
+    }).await?;
+    for (index, source) in sources.iter().enumerate() {
+        if source.entry.kind != EntryKind::File {
+            continue;
+        }
+        println!("Sending {}", source.entry.path);
+        let mut file = File::open(&source.path)?;
+        let compression = compression::choose(sender.compression, &source.entry.path);
+        sender.send_info(&InfoMessage::FileBegin { index, compression }).await?;
+        sender.send_stream(&mut file, 0, compression).await?;
+        sender.send_info(&InfoMessage::FileEnd { index }).await?;
+    }
+    sender.send_end().await
+}
+
+/// Ask the user whether the receiver shows the same verification code
+async fn confirm_code() -> Result<bool> {
+    print!("Does the receiver show the same code? [y/N] ");
+    std::io::stdout().flush()?;
+    let line = crate::input::read_line(None).await?.unwrap_or_default();
+    Ok(matches!(line.trim(), "y" | "Y" | "yes"))
+}
+
+pub(crate) async fn main(paths: &[String], options: Options) -> Result<()> {
+    if options.confirm && paths.iter().any(|path| path == "-") {
+        bail!("--confirm reads the answer from standard input, it can not be sent at the same time");
+    }
+    // The web receiver only knows single files, --compress is ruled out by the arguments
+    if options.web {
+        if options.challenges && options.challenge_side.receiver() {
+            bail!("--web can not be combined with challenges on the receiving side");
+        }
+        if !matches!(paths, [path] if path == "-" || !Path::new(path).is_dir()) {
+            bail!("--web sends a single file, browsers can not receive several files or a directory");
+        }
+    }
+    let transfer = match paths {
+        [path] if path == "-" || !Path::new(path).is_dir() => {
+            let mut input = clio::Input::new(path)?;
+            let name = Path::new(path)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .filter(|_| !input.is_std())
+                .unwrap_or("file")
+                .to_owned();
+            // Only seekable inputs can be hashed up front and resumed later
+            let resumable = if input.can_seek() {
+                let content_hash = resume::content_hash(&mut input)?;
+                let transfer_id = options.transfer_id.clone().unwrap_or_else(resume::new_transfer_id);
+                Some((transfer_id, content_hash))
+            } else {
+                None
+            };
+            Transfer::Single { input, name, resumable }
+        }
+        _ => {
+            if paths.iter().any(|path| path == "-") {
+                bail!("Standard input can only be sent on its own");
+            }
+            if options.transfer_id.is_some() {
+                warn!("Multi-file transfers can not be resumed, ignoring --resume");
+            }
+            let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+            Transfer::Tree(manifest::collect(&paths)?)
+        }
+    };
+    let identity = Identity::load_or_create()?;
+    let challenge = challenge::Registry::builtin()
+        .get(&options.challenge)
+        .ok_or_else(|| anyhow!("Unknown challenge {}", options.challenge))?;
+    let transfer_id = match &transfer {
+        Transfer::Single { resumable: Some((transfer_id, _)), .. } => Some(transfer_id.clone()),
+        _ => None,
+    };
 
-pub(crate) async fn main(filename: &str) -> Result<()> {
     // Create a MediaEngine object to configure the supported codec
     let mut m = MediaEngine::default();
 
@@ -63,7 +410,6 @@ pub(crate) async fn main(filename: &str) -> Result<()> {
             ],
             username: "6fb0f47d8cb4265a38814e9d".to_owned(),
             credential: "fgSXLhtt0s2cUy9C".to_owned(),
-            ..Default::default()
         }],
         ..Default::default()
     };
@@ -79,7 +425,7 @@ pub(crate) async fn main(filename: &str) -> Result<()> {
     // Set the handler for Peer connection state
     // This will notify you when the peer has connected/disconnected
     peer_connection.on_peer_connection_state_change(Box::new(
-        capture!(with done_tx = done_tx.clone(), move |s: RTCPeerConnectionState| {
+        capture!(with done_tx = done_tx.clone(), clone transfer_id, move |s: RTCPeerConnectionState| {
             info!("Peer Connection State has changed: {s}");
 
             if s == RTCPeerConnectionState::Failed {
@@ -87,6 +433,9 @@ pub(crate) async fn main(filename: &str) -> Result<()> {
                 // Use webrtc.PeerConnectionStateDisconnected if you are interested in detecting faster timeout.
                 // Note that the PeerConnection may come back from PeerConnectionStateDisconnected.
                 error!("Peer Connection has gone to failed exiting");
+                if let Some(transfer_id) = &transfer_id {
+                    println!("Transfer interrupted, run again with --resume {transfer_id} to continue");
+                }
                 let _ = done_tx.try_send(());
             }
 
@@ -94,9 +443,15 @@ pub(crate) async fn main(filename: &str) -> Result<()> {
         }),
     ));
 
+    // Wake the sender loop whenever the outgoing queue drains below the low watermark
+    let buffer_low = Arc::new(Notify::new());
+    data_channel.set_buffered_amount_low_threshold(options.buffer_low).await;
+    data_channel.on_buffered_amount_low(Box::new(capture!(clone buffer_low, move || {
+        buffer_low.notify_one();
+        Box::pin(async {})
+    }))).await;
+
     // Register channel opening handling
-    let d1 = Arc::clone(&data_channel);
-    let mut input = clio::Input::new(filename)?;
     data_channel.on_close({
         let done_tx = done_tx.clone();
         Box::new(move || {
@@ -107,73 +462,6 @@ pub(crate) async fn main(filename: &str) -> Result<()> {
         })
     }
     );
-    data_channel.on_open(Box::new(capture!(with done_tx = done_tx.clone(), move || {
-        println!("Ready to send data");
-        let mut buffer = BytesMut::zeroed(CHUNK_SIZE);
-        Box::pin(async move {
-            let len: usize = input.len().expect("Can only send files of known size") as usize;
-            let begin_message = serde_json::to_string(&InfoMessage::Begin { 
-                fileinfo: FileInfo { filename: "file".to_owned(), filesize: Some(len) },
-            }).unwrap();
-            match d1.send_text(begin_message).await {
-                Ok(_) => (),
-                Err(e) => {
-                    error!("Error sending begin message: {e}");
-                    done_tx.try_send(()).unwrap();
-                    return;
-                }
-            }
-
-            let mut bytes_until_wordle = BYTES_UNTIL_WORDLE;
-            loop {
-                buffer.resize(CHUNK_SIZE, 0u8);
-                if bytes_until_wordle < buffer.len() {
-                    buffer.truncate(bytes_until_wordle);
-                }
-                let n = input.read(buffer.as_mut());
-                match n {
-                    Ok(0) => {
-                        info!("File done");
-                        break;
-                    }
-                    Ok(n) => {
-                        bytes_until_wordle -= n;
-                        if bytes_until_wordle == 0 {
-                            println!("Suspicious activity detected");
-                            println!("To prove that you are not a robot, solve a wordle");
-                            match Wordle::play() {
-                                Some(_) => {}
-                                None => {
-                                    println!("Only a true robot is unable to solve wordle. Terminating transfer");
-                                    done_tx.try_send(()).unwrap();
-                                    break;
-                                }
-                            }
-                            println!("Alright, looks like you are human");
-                            bytes_until_wordle = BYTES_UNTIL_WORDLE;
-                        }
-                        buffer.truncate(n);
-                        println!("Sending {} bytes", n);
-
-                        d1.send(&Bytes::from(buffer.clone())).await.unwrap();
-                    }
-                    Err(e) => {
-                        error!("Error reading from data file: {e}");
-                        break;
-                    }
-                }
-            }
-
-            let end_message = serde_json::to_string(&InfoMessage::End { }).unwrap();
-            match d1.send_text(end_message).await {
-                Ok(_) => (),
-                Err(e) => {
-                    error!("Error sending end message: {e}");
-                }
-            }
-        })
-    })));
-
     // Create an offer to send to the browser
     let offer = peer_connection.create_offer(None).await?;
 
@@ -192,17 +480,36 @@ pub(crate) async fn main(filename: &str) -> Result<()> {
 
     let signalling = crate::signalling::SignallingClient::new("http://64.188.74.63".to_owned());
 
-    let session_id = if let Some(local_desc) = peer_connection.local_description().await {
-        let x = signalling.post_offer(local_desc).await?;
-        println!("Session ID: {}", x.identifier);
+    let key_pair = KeyPair::generate();
+    // The secret half of the code never leaves this machine, the receiver has to prove it knows it.
+    // Browsers can not do the key exchange, so they only get a code without one
+    let secret = (!options.web).then(pake::new_secret);
+    let pake = secret.as_deref().map(|secret| Pake::start(secret, key_pair.public.as_bytes()));
+    let local_desc = peer_connection
+        .local_description()
+        .await
+        .expect("generate local_description failed!");
+    let offer_sdp = local_desc.sdp.clone();
+    let session_id = {
+        let x = signalling.post_offer(local_desc, key_pair.public_base64(), pake.as_ref().map(Pake::message_base64), secret.as_deref()).await?;
+        let code = SessionCode { id: x.identifier.clone(), secret: secret.clone() };
+        println!("Session code: {code}");
+        if options.web {
+            println!("Browser mode: whoever answers first with this code gets the transfer, keep it private");
+        }
+        println!("Signing as {}", identity.public_base64());
+        if !options.challenges {
+            println!("Challenges are off, the receiver will see that they were not enforced");
+        }
+        if let Some(transfer_id) = &transfer_id {
+            println!("Transfer ID: {transfer_id}");
+        }
         x
-    } else {
-        panic!("generate local_description failed!");
     };
 
     // Wait for the answer to be pasted
 
-    let answer: RTCSessionDescription = loop {
+    let answer: AnswerPeerTo = loop {
         let timeout = tokio::time::sleep(Duration::from_millis(1500));
         tokio::pin!(timeout);
         tokio::select! {
@@ -210,7 +517,7 @@ pub(crate) async fn main(filename: &str) -> Result<()> {
 
                 let result = signalling.get_answer(&session_id.identifier).await;
                 match result {
-                    Ok(answer) => {break answer.client.web_rtc.answer;}
+                    Ok(answer) => {break answer.client;}
                     Err(_) => {
                         continue;
                     }
@@ -219,8 +526,118 @@ pub(crate) async fn main(filename: &str) -> Result<()> {
         }
     };
 
+    // Everything on the data channel is encrypted with a key only the two peers know,
+    // bound to the code's secret and to both DTLS certificates
+    let peer = crypto::parse_peer_key(answer.public_key.as_deref().unwrap_or_default(), &key_pair.public)?;
+    match &peer.identity {
+        Some(identity) if options.recipients.is_empty() || options.recipients.contains(identity) => {
+            println!("Receiver identified as {}", identity::describe(identity));
+        }
+        Some(identity) => bail!("Receiver {} is not among the --to recipients", identity::describe(identity)),
+        None if !options.recipients.is_empty() => bail!("Receiver did not identify itself, refusing to send"),
+        None => {}
+    }
+    let peer_key = peer.ephemeral;
+    let remote_desc = answer.web_rtc.open(secret.as_deref())?.answer;
+    let binding = match &pake {
+        Some(pake) => {
+            let peer_pake = answer
+                .pake
+                .as_deref()
+                .ok_or_else(|| anyhow!("Receiver did not prove it knows the session code, refusing to send"))?;
+            let pake_key = pake.finish(peer_pake, &pake.message_base64(), peer_pake)?;
+            Some(Binding::new(pake_key, &offer_sdp, &remote_desc.sdp)?)
+        }
+        None => None,
+    };
+    let session = Arc::new(Session::new(
+        &key_pair.session_key(&peer_key, &key_pair.public, &peer_key, binding.as_ref())?,
+    ));
+    let code = crypto::verification_code(&offer_sdp, &remote_desc.sdp, &key_pair.public, &peer_key)?;
+    println!("Verification code: {code}");
+    println!("Make sure the receiver sees the same code");
+
+    // Control messages flowing back from the receiver
+    let (control_tx, control_rx) = tokio::sync::mpsc::channel::<InfoMessage>(8);
+    data_channel.on_message(Box::new(capture!(clone session, move |msg: DataChannelMessage| {
+        if msg.is_string {
+            match session.open_text(&msg.data).and_then(|data| Ok(serde_json::from_slice::<InfoMessage>(&data)?)) {
+                // Only there to keep the connection busy, must not crowd out real replies
+                Ok(InfoMessage::KeepAlive {}) => {}
+                Ok(info) => {
+                    let _ = control_tx.try_send(info);
+                }
+                Err(e) => error!("Malformed control message: {e}"),
+            }
+        }
+        Box::pin(async {})
+    })));
+
+    let confirm = options.confirm;
+    let side = options.challenges.then_some(options.challenge_side);
+    let policy = ChallengePolicy {
+        enforced: options.challenges,
+        challenge: options.challenges.then(|| options.challenge.clone()),
+        side,
+        automated: side.is_some_and(challenge::Side::sender) && challenge::solver_plays(&options.challenge),
+    };
+    let receiver_challenge = side.is_some_and(challenge::Side::receiver).then(|| InfoMessage::ReceiverChallenge {
+        challenge: options.challenge.clone(),
+        interval: options.challenge_interval as u64,
+        distribution: options.interval_distribution,
+        settings: options.challenge_settings,
+    });
+    let interval = Interval { distribution: options.interval_distribution, mean: options.challenge_interval as u64 };
+    let mut pacing = Pacing::new(options.seed, interval, options.jitter, options.chunk_sizes);
+    let password = options.password;
+    let mut sender = Sender {
+        channel: Arc::clone(&data_channel),
+        buffer_high: options.buffer_high,
+        buffer_low,
+        challenge: side.is_some_and(challenge::Side::sender).then_some(challenge),
+        challenge_settings: options.challenge_settings,
+        policy,
+        bytes_until_challenge: pacing.next_interval() as usize,
+        sent: Arc::new(AtomicU64::new(0)),
+        total: None,
+        pacing,
+        control_rx,
+        compression: options.compression,
+        session,
+        identity,
+        transcript: Transcript::default(),
+    };
+    data_channel.on_open(Box::new(capture!(with done_tx = done_tx.clone(), move || {
+        println!("Ready to send data");
+        Box::pin(async move {
+            if confirm && !confirm_code().await.unwrap_or(false) {
+                error!("Verification code rejected, the connection may be intercepted");
+                done_tx.try_send(()).unwrap();
+                return;
+            }
+            let result = async {
+                if let Some(password) = &password {
+                    authenticate(&mut sender, password).await?;
+                }
+                if let Some(challenge) = receiver_challenge {
+                    sender.send_info(&challenge).await?;
+                }
+                match transfer {
+                    Transfer::Single { input, name, resumable } => {
+                        send_single(&mut sender, input, name, resumable).await
+                    }
+                    Transfer::Tree(sources) => send_tree(&mut sender, sources).await,
+                }
+            }.await;
+            if let Err(e) = result {
+                error!("Transfer failed: {e}");
+                done_tx.try_send(()).unwrap();
+            }
+        })
+    })));
+
     // Apply the answer as the remote description
-    peer_connection.set_remote_description(answer).await?;
+    peer_connection.set_remote_description(remote_desc).await?;
 
     //println!("Press ctrl-c to stop");
     tokio::select! {
@@ -1,14 +1,83 @@
 use reqwest::Client;
+use crate::crypto;
+use anyhow::bail;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
-use std::fmt::Display;
-use log::debug;
 
 pub struct SignallingClient {
     base_url: String,
     client: Client
 }
 
+/// What users pass around: `<id>-<secret>`.
+///
+/// Only `id` is ever sent to the signalling server, `secret` feeds the
+/// password-authenticated key exchange between the two peers. Codes of web
+/// senders, and of senders in `--web` mode, have no secret part.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionCode {
+    pub id: String,
+    pub secret: Option<String>,
+}
+
+impl FromStr for SessionCode {
+    type Err = anyhow::Error;
+
+    fn from_str(code: &str) -> anyhow::Result<Self> {
+        let code = code.trim();
+        let (id, secret) = match code.split_once('-') {
+            Some((id, secret)) => (id, Some(secret.to_ascii_lowercase())),
+            None => (code, None),
+        };
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+            bail!("Malformed session code {code:?}");
+        }
+        if secret.as_deref() == Some("") {
+            bail!("Malformed session code {code:?}");
+        }
+        Ok(SessionCode { id: id.to_owned(), secret })
+    }
+}
+
+impl fmt::Display for SessionCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.secret {
+            Some(secret) => write!(f, "{}-{secret}", self.id),
+            None => write!(f, "{}", self.id),
+        }
+    }
+}
+
+/// A session description as stored by the signalling server: encrypted with
+/// the code's secret, or in the clear for web peers that have no secret
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Sealed<T> {
+    Encrypted(String),
+    Plain(T),
+}
+
+impl<T: Serialize + DeserializeOwned> Sealed<T> {
+    pub fn seal(value: T, secret: Option<&str>) -> anyhow::Result<Self> {
+        Ok(match secret {
+            Some(secret) => Sealed::Encrypted(crypto::seal_description(secret, &value)?),
+            None => Sealed::Plain(value),
+        })
+    }
+
+    pub fn open(self, secret: Option<&str>) -> anyhow::Result<T> {
+        match (self, secret) {
+            (Sealed::Encrypted(sealed), Some(secret)) => crypto::open_description(secret, &sealed),
+            (Sealed::Plain(value), None) => Ok(value),
+            (Sealed::Encrypted(_), None) => bail!("Session description is encrypted, the session code is missing its secret part"),
+            (Sealed::Plain(_), Some(_)) => bail!("Peer sent its session description unencrypted, refusing to connect"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OfferTo {
     pub peer: OfferPeerTo
@@ -18,8 +87,11 @@ pub struct OfferTo {
 pub struct OfferPeerTo {
     #[serde(rename = "publicKey")]
     pub public_key: String,
+    /// Sender's PAKE message, absent for web senders
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pake: Option<String>,
     #[serde(rename = "webRTC")]
-    pub web_rtc: OfferPeerWebRTCTo
+    pub web_rtc: Sealed<OfferPeerWebRTCTo>
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,8 +113,10 @@ pub struct AnswerTo {
 pub struct AnswerPeerTo {
     #[serde(rename = "publicKey")]
     pub public_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pake: Option<String>,
     #[serde(rename = "webRTC")]
-    pub web_rtc: AnswerPeerWebRTCTo
+    pub web_rtc: Sealed<AnswerPeerWebRTCTo>
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,13 +129,13 @@ impl SignallingClient {
         Self { base_url, client: Default::default() }
     }
 
-    pub async fn post_offer(&self, offer: RTCSessionDescription) -> anyhow::Result<OfferResponse> {
+    /// `secret` is the secret half of the session code the offer will belong to, none for browser receivers
+    pub async fn post_offer(&self, offer: RTCSessionDescription, public_key: String, pake: Option<String>, secret: Option<&str>) -> anyhow::Result<OfferResponse> {
         let body = OfferTo {
             peer: OfferPeerTo {
-                public_key: "none".to_string(),
-                web_rtc: OfferPeerWebRTCTo {
-                    offer
-                }
+                public_key,
+                pake,
+                web_rtc: Sealed::seal(OfferPeerWebRTCTo { offer }, secret)?
             }
         };
         let res = self.client.post(format!("{}/api/peer", self.base_url))
@@ -71,13 +145,13 @@ impl SignallingClient {
         Ok(res.json().await?)
     }
 
-    pub async fn post_answer(&self, id: String, answer: RTCSessionDescription) -> anyhow::Result<()> {
+    pub async fn post_answer(&self, code: &SessionCode, answer: RTCSessionDescription, public_key: String, pake: Option<String>) -> anyhow::Result<()> {
+        let id = &code.id;
         let body = AnswerTo {
             client: AnswerPeerTo {
-                public_key: Option::from("none".to_string()),
-                web_rtc: AnswerPeerWebRTCTo {
-                    answer
-                }
+                public_key: Some(public_key),
+                pake,
+                web_rtc: Sealed::seal(AnswerPeerWebRTCTo { answer }, code.secret.as_deref())?
             }
         };
         let res = self.client.post(format!("{}/api/peer/{id}/client", self.base_url))
@@ -88,7 +162,8 @@ impl SignallingClient {
         Ok(())
     }
 
-    pub async fn get_offer(&self, id: &str) -> anyhow::Result<OfferTo> {
+    pub async fn get_offer(&self, code: &SessionCode) -> anyhow::Result<OfferTo> {
+        let id = &code.id;
         let res = self.client.get(format!("{}/api/peer/{id}", self.base_url))
             .send()
             .await?;
@@ -102,4 +177,33 @@ impl SignallingClient {
         let x = res.text().await?;
         Ok(serde_json::from_str::<AnswerTo>(&x)?)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_session_codes() {
+        let code: SessionCode = " 4242-Crane-Slate-Audio ".parse().unwrap();
+        assert_eq!(code, SessionCode { id: "4242".to_owned(), secret: Some("crane-slate-audio".to_owned()) });
+        assert_eq!(code.to_string(), "4242-crane-slate-audio");
+        assert_eq!("4242".parse::<SessionCode>().unwrap().secret, None);
+        for bad in ["", "-crane", "4242-", "42 42", "42/42-crane"] {
+            assert!(bad.parse::<SessionCode>().is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn sealed_needs_the_right_secret() {
+        let sealed = Sealed::seal(OfferResponse { identifier: "4242".to_owned() }, Some("crane-slate-audio")).unwrap();
+        assert!(matches!(sealed, Sealed::Encrypted(_)));
+        assert!(sealed.clone().open(Some("crane-slate-adieu")).is_err());
+        assert!(sealed.clone().open(None).is_err());
+        assert_eq!(sealed.open(Some("crane-slate-audio")).unwrap().identifier, "4242");
+
+        let plain = Sealed::seal(OfferResponse { identifier: "4242".to_owned() }, None).unwrap();
+        assert!(plain.clone().open(Some("crane-slate-audio")).is_err());
+        assert_eq!(plain.open(None).unwrap().identifier, "4242");
+    }
+}
@@ -0,0 +1,134 @@
+use crate::challenge::{Guesses, Outcome, Settings};
+use crate::wordle::GuessResult::{Invalid, Loss, MissedHint, TryAgain, Win};
+use crate::wordle::GuessLetterResult::Green;
+use crate::wordle::{self, GuessLetterResult, Wordle};
+use anyhow::Result;
+use log::info;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static ANSWER: OnceLock<String> = OnceLock::new();
+
+/// Let the solver play every Wordle challenge from now on, so transfer tests need no human.
+/// Only built with the `solver` feature and only turned on by `--solve-challenges`
+pub(crate) fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub(crate) fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Make every Wordle game have this answer, set by `--wordle-answer` once the word list is chosen
+pub(crate) fn fix_answer(word: &str) -> Result<()> {
+    Wordle::with_word(&Settings::default(), word)?;
+    let _ = ANSWER.set(word.to_owned());
+    Ok(())
+}
+
+pub(crate) fn answer() -> Option<&'static str> {
+    ANSWER.get().map(String::as_str)
+}
+
+/// Words that fit every hint so far. Guessing only those also keeps hard mode happy
+pub(crate) struct Solver {
+    candidates: Vec<&'static str>,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Solver { candidates: wordle::dictionary().allowed().collect() }
+    }
+}
+
+impl Solver {
+    /// The candidate whose distinct letters are the most common among the candidates
+    pub fn next_guess(&self) -> Option<&'static str> {
+        let mut counts: Vec<(char, usize)> = Vec::new();
+        for word in &self.candidates {
+            for letter in distinct(word) {
+                match counts.iter_mut().find(|(known, _)| *known == letter) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((letter, 1)),
+                }
+            }
+        }
+        let weight = |word: &str| -> usize {
+            distinct(word).map(|letter| counts.iter().find(|(known, _)| *known == letter).map_or(0, |(_, count)| *count)).sum()
+        };
+        self.candidates.iter().copied().max_by_key(|word| weight(word))
+    }
+
+    /// Drop every candidate that would have scored `guess` differently
+    pub fn learn(&mut self, guess: &str, result: &[GuessLetterResult; 5]) {
+        self.candidates.retain(|word| wordle::score(word, guess) == *result);
+    }
+
+    /// Play a game to the end, returns the final result and the guesses that counted with their scores
+    pub fn solve(&mut self, game: &mut Wordle) -> (wordle::GuessResult, Vec<(&'static str, [GuessLetterResult; 5])>) {
+        let mut guesses = Vec::new();
+        loop {
+            let Some(guess) = self.next_guess() else {
+                return (Loss { result: None }, guesses);
+            };
+            match game.guess(guess) {
+                TryAgain { result } => {
+                    guesses.push((guess, result));
+                    self.learn(guess, &result);
+                }
+                // Every candidate is an allowed word that fits the hints
+                Invalid | MissedHint { .. } => unreachable!("solver guessed {guess} out of turn"),
+                Win => {
+                    guesses.push((guess, [Green; 5]));
+                    return (Win, guesses);
+                }
+                Loss { result } => {
+                    guesses.extend(result.map(|result| (guess, result)));
+                    return (Loss { result }, guesses);
+                }
+            }
+        }
+    }
+}
+
+fn distinct(word: &str) -> impl Iterator<Item = char> + '_ {
+    word.char_indices().filter(move |&(i, letter)| !word[..i].contains(letter)).map(|(_, letter)| letter)
+}
+
+/// Stands in for `Wordle::play`, spectators see every scored guess like they would a human's
+pub(crate) fn play(settings: &Settings, spectators: &Guesses) -> Outcome {
+    let mut game = Wordle::new(settings);
+    let (result, guesses) = Solver::default().solve(&mut game);
+    for (_, result) in &guesses {
+        let _ = spectators.send(*result);
+    }
+    info!("Solver guessed {}", guesses.iter().map(|(guess, _)| *guess).collect::<Vec<_>>().join(", "));
+    match result {
+        Win => Outcome::Pass,
+        _ => Outcome::Fail,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenge::Difficulty;
+
+    #[test]
+    fn solves_seeded_games() {
+        let settings = Settings { difficulty: Difficulty::Normal, hard_mode: true, ..Settings::default() };
+        for seed in 0..100 {
+            let mut game = Wordle::with_seed(&settings, seed);
+            assert_eq!(Solver::default().solve(&mut game).0, Win, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn narrows_down_a_known_word() {
+        let mut game = Wordle::with_word(&Settings::default(), "crane").unwrap();
+        let (result, guesses) = Solver::default().solve(&mut game);
+        assert_eq!(result, Win);
+        assert_eq!(guesses.last(), Some(&("crane", [Green; 5])));
+    }
+}
@@ -0,0 +1,111 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+const STATS_FILE: &str = "wordle_stats.json";
+const BAR_WIDTH: u32 = 30;
+// Rows the distribution always shows, like the attempts of a normal game
+const MIN_ROWS: usize = 6;
+
+/// Where results of past games are kept, created on first use
+fn data_dir() -> Result<PathBuf> {
+    let dir = dirs::data_dir()
+        .ok_or_else(|| anyhow!("Can not find a data directory for this user"))?
+        .join("instant-pastebin");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Wordle results of this user across transfers
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Stats {
+    pub played: u32,
+    pub won: u32,
+    #[serde(rename = "currentStreak")]
+    pub current_streak: u32,
+    #[serde(rename = "maxStreak")]
+    pub max_streak: u32,
+    /// Games won with one guess, two guesses and so on
+    pub guesses: Vec<u32>,
+}
+
+impl Stats {
+    /// Nothing played yet if there is no file
+    pub fn load() -> Result<Self> {
+        let path = data_dir()?.join(STATS_FILE);
+        match fs::read_to_string(&path) {
+            Ok(data) => serde_json::from_str(&data).with_context(|| format!("Statistics file {} is corrupted", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Stats::default()),
+            Err(e) => Err(e).with_context(|| format!("Can not read {}", path.display())),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = data_dir()?.join(STATS_FILE);
+        fs::write(&path, serde_json::to_string_pretty(self)?).with_context(|| format!("Can not write {}", path.display()))
+    }
+
+    /// Count a finished game, won with this many guesses or lost
+    pub fn record(&mut self, guesses: Option<usize>) {
+        self.played += 1;
+        let Some(guesses) = guesses else {
+            self.current_streak = 0;
+            return;
+        };
+        self.won += 1;
+        self.current_streak += 1;
+        self.max_streak = self.max_streak.max(self.current_streak);
+        if self.guesses.len() < guesses {
+            self.guesses.resize(guesses, 0);
+        }
+        self.guesses[guesses - 1] += 1;
+    }
+
+    /// Record a game in the stored statistics, returns them for display
+    pub fn record_game(guesses: Option<usize>) -> Result<Self> {
+        let mut stats = Stats::load()?;
+        stats.record(guesses);
+        stats.save()?;
+        Ok(stats)
+    }
+
+    pub fn win_rate(&self) -> u32 {
+        (self.won * 100).checked_div(self.played).unwrap_or(0)
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Played: {}, win rate: {}%", self.played, self.win_rate())?;
+        writeln!(f, "Current streak: {}, best streak: {}", self.current_streak, self.max_streak)?;
+        write!(f, "Guess distribution:")?;
+        let most = self.guesses.iter().copied().max().unwrap_or(0).max(1);
+        for row in 0..self.guesses.len().max(MIN_ROWS) {
+            let count = self.guesses.get(row).copied().unwrap_or(0);
+            let bar: String = "█".repeat((count * BAR_WIDTH).div_ceil(most) as usize) + if count > 0 { " " } else { "" };
+            write!(f, "\n{:>2} | {bar}{count}", row + 1)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streaks_and_distribution() {
+        let mut stats = Stats::default();
+        stats.record(Some(3));
+        stats.record(Some(8));
+        stats.record(None);
+        stats.record(Some(3));
+        assert_eq!(stats.played, 4);
+        assert_eq!(stats.win_rate(), 75);
+        assert_eq!(stats.current_streak, 1);
+        assert_eq!(stats.max_streak, 2);
+        assert_eq!(stats.guesses, vec![0, 0, 2, 0, 0, 0, 0, 1]);
+    }
+}
@@ -0,0 +1,220 @@
+use crate::challenge::{Difficulty, Outcome, Settings};
+use rand::Rng;
+use rand::seq::SliceRandom;
+use std::time::Duration;
+
+const SIZE: usize = 4;
+const BOX: usize = 2;
+
+type Grid = [[u8; SIZE]; SIZE];
+
+#[derive(Debug, PartialEq)]
+pub enum Move {
+    Right,
+    Wrong,
+    /// Not an empty cell, or not a digit that fits the board at all
+    Invalid,
+    Win,
+    Loss,
+}
+
+/// A 4x4 Sudoku with 2x2 boxes, small enough to solve in a terminal while the transfer waits
+pub struct Sudoku {
+    solution: Grid,
+    board: Grid,
+    pub mistakes_left: u8,
+}
+
+impl Sudoku {
+    /// Harder puzzles reveal fewer digits and forgive fewer mistakes
+    pub fn new(settings: &Settings) -> Self {
+        let (blanks, mistakes_left) = match settings.difficulty {
+            Difficulty::Easy => (6, 3),
+            Difficulty::Normal => (8, 2),
+            Difficulty::Hard => (10, 1),
+        };
+        let solution = solved_grid(&mut rand::rng());
+        Sudoku { board: blank_out(solution, blanks, &mut rand::rng()), solution, mistakes_left }
+    }
+
+    /// Rows and columns count from 1 like on the printed board
+    pub fn place(&mut self, row: usize, column: usize, digit: u8) -> Move {
+        if !(1..=SIZE).contains(&row) || !(1..=SIZE).contains(&column) || !(1..=SIZE as u8).contains(&digit) {
+            return Move::Invalid;
+        }
+        let (row, column) = (row - 1, column - 1);
+        if self.board[row][column] != 0 {
+            return Move::Invalid;
+        }
+        if self.solution[row][column] != digit {
+            self.mistakes_left -= 1;
+            return if self.mistakes_left == 0 { Move::Loss } else { Move::Wrong };
+        }
+        self.board[row][column] = digit;
+        if self.board == self.solution { Move::Win } else { Move::Right }
+    }
+
+    fn render(&self) -> String {
+        let mut lines = vec!["    1 2   3 4".to_owned()];
+        for (number, row) in self.board.iter().enumerate() {
+            if number == BOX {
+                lines.push("    ----+----".to_owned());
+            }
+            let digits: Vec<String> =
+                row.iter().map(|&digit| if digit == 0 { ".".to_owned() } else { digit.to_string() }).collect();
+            lines.push(format!(" {}  {} | {}", number + 1, digits[..BOX].join(" "), digits[BOX..].join(" ")));
+        }
+        lines.join("\n")
+    }
+
+    pub async fn play(settings: &Settings) -> Outcome {
+        let mut game = Sudoku::new(settings);
+        eprintln!("Fill in the Sudoku: every row, column and box holds 1 to 4 once");
+        eprintln!("Answer with row, column and digit, like 2 3 4. You can afford {} mistakes", game.mistakes_left - 1);
+        let timeout = settings.answer_timeout.map(Duration::from_secs);
+        loop {
+            eprintln!("{}", game.render());
+            let line = match crate::input::read_line(timeout).await {
+                Ok(Some(line)) => line,
+                Ok(None) => return Outcome::Abort,
+                Err(_) => {
+                    eprintln!("Time is up");
+                    return Outcome::Fail;
+                }
+            };
+            let numbers: Vec<u8> = line.chars().filter_map(|c| c.to_digit(10)).map(|digit| digit as u8).collect();
+            let [row, column, digit] = numbers[..] else {
+                eprintln!("Answer with row, column and digit");
+                continue;
+            };
+            match game.place(row as usize, column as usize, digit) {
+                Move::Win => {
+                    eprintln!("You win!");
+                    return Outcome::Pass;
+                }
+                Move::Loss => {
+                    eprintln!("Too many mistakes");
+                    return Outcome::Fail;
+                }
+                Move::Right => {}
+                Move::Wrong => eprintln!("{digit} does not go there"),
+                Move::Invalid => eprintln!("Pick an empty cell and a digit from 1 to 4"),
+            }
+        }
+    }
+}
+
+/// A fixed valid grid, shuffled by moves that keep it valid
+fn solved_grid(rng: &mut impl Rng) -> Grid {
+    let mut digits = [1, 2, 3, 4];
+    digits.shuffle(rng);
+    let mut rows = shuffled_lines(rng);
+    let mut columns = shuffled_lines(rng);
+    if rng.random() {
+        std::mem::swap(&mut rows, &mut columns);
+    }
+    let mut grid = [[0; SIZE]; SIZE];
+    for (row, &from_row) in rows.iter().enumerate() {
+        for (column, &from_column) in columns.iter().enumerate() {
+            grid[row][column] = digits[(from_row % BOX * BOX + from_row / BOX + from_column) % SIZE];
+        }
+    }
+    grid
+}
+
+/// Row or column order with bands shuffled, and lines shuffled within each band
+fn shuffled_lines(rng: &mut impl Rng) -> [usize; SIZE] {
+    let mut bands = [0, 1];
+    bands.shuffle(rng);
+    let mut lines = [0; SIZE];
+    for (position, band) in bands.into_iter().enumerate() {
+        let mut within = [0, 1];
+        within.shuffle(rng);
+        for (offset, line) in within.into_iter().enumerate() {
+            lines[position * BOX + offset] = band * BOX + line;
+        }
+    }
+    lines
+}
+
+/// Empty up to `blanks` cells, keeping only those that leave a single solution
+fn blank_out(solution: Grid, blanks: usize, rng: &mut impl Rng) -> Grid {
+    let mut cells: Vec<(usize, usize)> = cells().collect();
+    cells.shuffle(rng);
+    let mut board = solution;
+    let mut removed = 0;
+    for (row, column) in cells {
+        if removed == blanks {
+            break;
+        }
+        board[row][column] = 0;
+        if count_solutions(&mut board.clone(), 2) == 1 {
+            removed += 1;
+        } else {
+            board[row][column] = solution[row][column];
+        }
+    }
+    board
+}
+
+/// Solutions of a partly filled grid, stops counting at `limit`
+fn count_solutions(grid: &mut Grid, limit: usize) -> usize {
+    let Some((row, column)) = cells().find(|&(row, column)| grid[row][column] == 0) else {
+        return 1;
+    };
+    let mut count = 0;
+    for digit in 1..=SIZE as u8 {
+        if fits(grid, row, column, digit) {
+            grid[row][column] = digit;
+            count += count_solutions(grid, limit - count);
+            grid[row][column] = 0;
+            if count >= limit {
+                break;
+            }
+        }
+    }
+    count
+}
+
+/// Every (row, column) of the grid
+fn cells() -> impl Iterator<Item = (usize, usize)> {
+    (0..SIZE).flat_map(|row| (0..SIZE).map(move |column| (row, column)))
+}
+
+fn fits(grid: &Grid, row: usize, column: usize, digit: u8) -> bool {
+    let (top, left) = (row / BOX * BOX, column / BOX * BOX);
+    (0..SIZE).all(|i| grid[row][i] != digit && grid[i][column] != digit)
+        && (top..top + BOX).all(|r| (left..left + BOX).all(|c| grid[r][c] != digit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn puzzles_are_valid_and_unique() {
+        let mut rng = rand::rng();
+        for _ in 0..50 {
+            let solution = solved_grid(&mut rng);
+            let mut check = [[0; SIZE]; SIZE];
+            for row in 0..SIZE {
+                for column in 0..SIZE {
+                    assert!(fits(&check, row, column, solution[row][column]));
+                    check[row][column] = solution[row][column];
+                }
+            }
+            let mut board = blank_out(solution, 10, &mut rng);
+            assert_eq!(board.iter().flatten().filter(|&&digit| digit == 0).count(), 10);
+            assert_eq!(count_solutions(&mut board, 2), 1);
+        }
+    }
+
+    #[test]
+    fn mistakes_run_out() {
+        let mut game = Sudoku::new(&Settings { difficulty: Difficulty::Normal, ..Settings::default() });
+        let (row, column) = cells().find(|&(row, column)| game.board[row][column] == 0).unwrap();
+        let wrong = game.solution[row][column] % SIZE as u8 + 1;
+        assert_eq!(game.place(row + 1, column + 1, wrong), Move::Wrong);
+        assert_eq!(game.place(row + 1, column + 1, wrong), Move::Loss);
+    }
+}
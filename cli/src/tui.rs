@@ -0,0 +1,146 @@
+use crate::challenge::TransferProgress;
+use crate::wordle::GuessLetterResult::{self, Gray, Green, Yellow};
+use anyhow::{bail, Result};
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::layout::{Alignment, Constraint, Layout, Position};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Gauge, Paragraph};
+use ratatui::Terminal;
+use std::io::{self, IsTerminal, Stdout};
+
+const LATIN_KEYBOARD: [&str; 3] = ["qwertyuiop", "asdfghjkl", "zxcvbnm"];
+const CYRILLIC_KEYBOARD: [&str; 3] = ["йцукенгшщзхъ", "фывапролджэ", "ячсмитьбю"];
+const PROMPT: &str = "Your guess: ";
+const MIN_WIDTH: u16 = 64;
+// Title, keyboard, message, prompt and progress around the board itself
+const WORDLE_CHROME: u16 = 13;
+
+/// Full-screen boards need a terminal to draw on and one to type into
+pub(crate) fn available() -> bool {
+    io::stdout().is_terminal() && io::stdin().is_terminal()
+}
+
+/// Everything the Wordle board shows
+pub(crate) struct WordleView<'a> {
+    pub guesses: &'a [(String, [GuessLetterResult; 5])],
+    pub attempts: usize,
+    pub cyrillic: bool,
+    pub hard_mode: bool,
+    pub message: &'a str,
+    pub progress: TransferProgress,
+}
+
+/// Alternate screen that is left again when dropped.
+///
+/// The terminal stays in line mode: answers are typed at the prompt, echoed
+/// by the terminal itself and read like any other line of input.
+pub(crate) struct Screen {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+}
+
+impl Screen {
+    /// Fails on terminals too small for a board of `rows` guesses, line mode does better there
+    pub fn open_wordle(rows: usize) -> Result<Self> {
+        let (width, height) = ratatui::crossterm::terminal::size()?;
+        if width < MIN_WIDTH || height < WORDLE_CHROME + rows as u16 {
+            bail!("Terminal is too small for the board");
+        }
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        Ok(Screen { terminal: Terminal::new(CrosstermBackend::new(io::stdout()))? })
+    }
+
+    pub fn draw_wordle(&mut self, view: &WordleView) -> Result<()> {
+        // Throw away whatever the terminal echoed since the last frame
+        self.terminal.clear()?;
+        self.terminal.draw(|frame| {
+            let [title, board, keyboard, message, prompt, progress] = Layout::vertical([
+                Constraint::Length(3),
+                Constraint::Length(view.attempts as u16),
+                Constraint::Length(5),
+                Constraint::Length(2),
+                Constraint::Length(2),
+                Constraint::Length(1),
+            ])
+            .areas(frame.area());
+
+            let mut heading = vec![Line::from("To prove that you are not a robot, solve a wordle")];
+            if view.hard_mode {
+                heading.push(Line::from("Hard mode: every revealed hint must be used in later guesses"));
+            }
+            frame.render_widget(Paragraph::new(heading).alignment(Alignment::Center), title);
+
+            let rows: Vec<Line> = (0..view.attempts)
+                .map(|row| match view.guesses.get(row) {
+                    Some((word, result)) => Line::from(
+                        word.chars().zip(result).map(|(letter, &result)| cell(letter, Some(result))).collect::<Vec<_>>(),
+                    ),
+                    None => Line::from(vec![cell('·', None); 5]),
+                })
+                .collect();
+            frame.render_widget(Paragraph::new(rows).alignment(Alignment::Center), board);
+
+            let layout = if view.cyrillic { CYRILLIC_KEYBOARD } else { LATIN_KEYBOARD };
+            let keys: Vec<Line> = std::iter::once(Line::default())
+                .chain(layout.iter().map(|row| {
+                    Line::from(row.chars().map(|key| cell(key, key_state(view.guesses, key))).collect::<Vec<_>>())
+                }))
+                .collect();
+            frame.render_widget(Paragraph::new(keys).alignment(Alignment::Center), keyboard);
+
+            let notice = Style::default().fg(Color::Yellow);
+            frame.render_widget(Paragraph::new(Span::styled(view.message, notice)).alignment(Alignment::Center), message);
+
+            frame.render_widget(Paragraph::new(PROMPT), prompt);
+            frame.set_cursor_position(Position::new(prompt.x + PROMPT.len() as u16, prompt.y));
+
+            let done = view.progress.done;
+            match view.progress.total {
+                Some(total) if total > 0 => {
+                    let gauge = Gauge::default()
+                        .gauge_style(Style::default().fg(Color::Blue))
+                        .ratio((done as f64 / total as f64).min(1.0))
+                        .label(format!("{done} of {total} bytes transferred, paused"));
+                    frame.render_widget(gauge, progress);
+                }
+                _ => frame.render_widget(Paragraph::new(format!("{done} bytes transferred, paused")), progress),
+            }
+        })?;
+        Ok(())
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), LeaveAlternateScreen);
+        let _ = self.terminal.show_cursor();
+    }
+}
+
+/// One letter tile, colored like the line mode
+fn cell(letter: char, state: Option<GuessLetterResult>) -> Span<'static> {
+    let style = match state {
+        Some(Green) => Style::default().bg(Color::Green).fg(Color::Black).add_modifier(Modifier::BOLD),
+        Some(Yellow) => Style::default().bg(Color::Yellow).fg(Color::Black).add_modifier(Modifier::BOLD),
+        Some(Gray) => Style::default().bg(Color::DarkGray).fg(Color::Gray),
+        None => Style::default(),
+    };
+    Span::styled(format!(" {} ", letter.to_uppercase()), style)
+}
+
+/// Best hint a letter got so far, green beats yellow beats gray
+fn key_state(guesses: &[(String, [GuessLetterResult; 5])], key: char) -> Option<GuessLetterResult> {
+    let rank = |state: &GuessLetterResult| match state {
+        Green => 2,
+        Yellow => 1,
+        Gray => 0,
+    };
+    guesses
+        .iter()
+        .flat_map(|(word, result)| word.chars().zip(result.iter()))
+        .filter(|(letter, _)| *letter == key)
+        .map(|(_, state)| *state)
+        .max_by_key(rank)
+}
@@ -0,0 +1,103 @@
+use crate::challenge::{Difficulty, Outcome, Settings};
+use crate::wordle::WORDS;
+use rand::prelude::IndexedRandom;
+use std::time::{Duration, Instant};
+
+/// Type a line of random words before the time runs out
+pub struct TypingTest {
+    text: String,
+    limit: Duration,
+    /// Words that may be mistyped and still pass
+    typos: usize,
+}
+
+impl TypingTest {
+    /// Harder tests are longer, faster and stricter
+    pub fn new(settings: &Settings) -> Self {
+        let (words, seconds, typos) = match settings.difficulty {
+            Difficulty::Easy => (4, 30, 1),
+            Difficulty::Normal => (6, 25, 1),
+            Difficulty::Hard => (8, 20, 0),
+        };
+        let text: Vec<&str> = WORDS.choose_multiple(&mut rand::rng(), words).copied().collect();
+        TypingTest { text: text.join(" "), limit: Duration::from_secs(seconds), typos }
+    }
+
+    /// Words typed wrong, missing or extra
+    pub fn mistakes(&self, typed: &str) -> usize {
+        let expected: Vec<&str> = self.text.split_whitespace().collect();
+        let typed: Vec<String> = typed.split_whitespace().map(str::to_lowercase).collect();
+        let wrong = expected.iter().zip(&typed).filter(|(expected, typed)| **expected != typed.as_str()).count();
+        wrong + expected.len().abs_diff(typed.len())
+    }
+
+    pub fn passes(&self, typed: &str) -> bool {
+        self.mistakes(typed) <= self.typos
+    }
+
+    /// The answer timeout can only make the test stricter
+    pub async fn play(settings: &Settings) -> Outcome {
+        let test = TypingTest::new(settings);
+        let limit = match settings.answer_timeout {
+            Some(seconds) => test.limit.min(Duration::from_secs(seconds)),
+            None => test.limit,
+        };
+        eprintln!("Type this line within {} seconds:", limit.as_secs());
+        eprintln!("{}", test.text);
+        let start = Instant::now();
+        let typed = match crate::input::read_line(Some(limit)).await {
+            Ok(Some(line)) => line,
+            Ok(None) => return Outcome::Abort,
+            Err(_) => {
+                eprintln!("Time is up");
+                return Outcome::Fail;
+            }
+        };
+        if !test.passes(&typed) {
+            eprintln!("{} words typed wrong", test.mistakes(&typed));
+            return Outcome::Fail;
+        }
+        eprintln!("Done in {:.1} seconds", start.elapsed().as_secs_f64());
+        Outcome::Pass
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test(typos: usize) -> TypingTest {
+        TypingTest { text: "crane slate audio".to_owned(), limit: Duration::from_secs(30), typos }
+    }
+
+    #[test]
+    fn counts_mistakes() {
+        let test = test(1);
+        assert_eq!(test.mistakes("crane slate audio"), 0);
+        assert_eq!(test.mistakes("  Crane  SLATE audio "), 0);
+        assert_eq!(test.mistakes("crane slat audio"), 1);
+        assert_eq!(test.mistakes("crane slate"), 1);
+        assert_eq!(test.mistakes("crane slate audio adieu"), 1);
+        assert_eq!(test.mistakes("slate audio"), 3);
+        assert_eq!(test.mistakes(""), 3);
+    }
+
+    #[test]
+    fn typo_allowance() {
+        assert!(test(1).passes("crane slat audio"));
+        assert!(!test(1).passes("crane slat audi"));
+        assert!(!test(0).passes("crane slat audio"));
+        assert!(test(0).passes("crane slate audio"));
+    }
+
+    #[test]
+    fn harder_is_longer_faster_stricter() {
+        let new = |difficulty| TypingTest::new(&Settings { difficulty, ..Settings::default() });
+        let (easy, normal, hard) = (new(Difficulty::Easy), new(Difficulty::Normal), new(Difficulty::Hard));
+        assert_eq!(easy.text.split_whitespace().count(), 4);
+        assert_eq!(hard.text.split_whitespace().count(), 8);
+        assert!(easy.limit > normal.limit && normal.limit > hard.limit);
+        assert_eq!(hard.typos, 0);
+        assert!(easy.passes(&easy.text));
+    }
+}
@@ -1,116 +1,383 @@
-use crate::wordle::GuessLetterResult::{GRAY, GREEN, YELLOW};
-use crate::wordle::GuessResult::{Loss, TryAgain, Win};
+use crate::wordle::GuessLetterResult::{Gray, Green, Yellow};
+use crate::wordle::GuessResult::{Invalid, Loss, MissedHint, TryAgain, Win};
+use crate::challenge::{Difficulty, Guesses, Outcome, Settings, TransferProgress};
+use crate::stats::Stats;
+use crate::tui;
+use anyhow::{bail, Context, Result};
 use colored::Colorize;
+use log::warn;
 use rand::prelude::IndexedRandom;
-use std::io;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+const WORD_LENGTH: usize = 5;
+const RUSSIAN: &str = include_str!("words/ru.txt");
+
+static DICTIONARY: OnceLock<Dictionary> = OnceLock::new();
+
+/// Answers and accepted guesses in one language
+pub(crate) struct Dictionary {
+    /// Answers at hard difficulty
+    all: Vec<String>,
+    /// Answers at easier difficulties
+    common: Vec<String>,
+    /// Every accepted guess, sorted for binary search
+    allowed: Vec<String>,
+}
+
+impl Dictionary {
+    /// A built-in language, or a file with one word per line
+    pub fn load(name: &str) -> Result<Self> {
+        match name {
+            "en" => Ok(Dictionary::english()),
+            "ru" => Dictionary::parse(RUSSIAN, "built-in Russian list"),
+            path => {
+                let data = fs::read_to_string(path).with_context(|| format!("Can not read word list {path}"))?;
+                Dictionary::parse(&data, path)
+            }
+        }
+    }
+
+    fn english() -> Self {
+        let mut allowed: Vec<String> = WORDS.iter().chain(&COMMON_WORDS).chain(&GUESSES).map(|&word| word.to_owned()).collect();
+        allowed.sort();
+        allowed.dedup();
+        Dictionary {
+            all: WORDS.iter().map(|&word| word.to_owned()).collect(),
+            common: COMMON_WORDS.iter().map(|&word| word.to_owned()).collect(),
+            allowed,
+        }
+    }
+
+    /// Every word of a list is both an answer and a guess, `#` starts a comment
+    fn parse(data: &str, source: &str) -> Result<Self> {
+        let mut words = Vec::new();
+        for (number, line) in data.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let word = normalize(line);
+            if word.chars().count() != WORD_LENGTH || !word.chars().all(char::is_alphabetic) {
+                bail!("{source}:{}: {line:?} is not a {WORD_LENGTH}-letter word", number + 1);
+            }
+            words.push(word);
+        }
+        if words.is_empty() {
+            bail!("{source} has no words");
+        }
+        words.sort();
+        words.dedup();
+        Ok(Dictionary { all: words.clone(), common: words.clone(), allowed: words })
+    }
+
+    fn is_allowed(&self, guess: &str) -> bool {
+        self.allowed.binary_search_by(|word| word.as_str().cmp(guess)).is_ok()
+    }
+
+    /// Every accepted guess, in order
+    #[cfg(feature = "solver")]
+    pub fn allowed(&self) -> impl Iterator<Item = &str> {
+        self.allowed.iter().map(String::as_str)
+    }
+}
+
+/// Play with `dictionary` from now on, only takes effect before the first game
+pub(crate) fn use_dictionary(dictionary: Dictionary) {
+    let _ = DICTIONARY.set(dictionary);
+}
+
+pub(crate) fn dictionary() -> &'static Dictionary {
+    DICTIONARY.get_or_init(Dictionary::english)
+}
+
+/// Lowercase, and ё counts as е like in most Russian word games
+fn normalize(word: &str) -> String {
+    word.to_lowercase().replace('ё', "е")
+}
 
 pub struct Wordle {
     word: &'static str,
     pub attempts_left: u8,
+    hard_mode: bool,
+    /// Letters revealed in their place so far, only tracked in hard mode
+    greens: [Option<char>; 5],
+    /// Fewest times each revealed letter has to appear in later guesses
+    revealed: Vec<(char, usize)>,
 }
 
 impl Default for Wordle {
     fn default() -> Self {
-        Wordle {
-            word: WORDS.choose(&mut rand::rng()).unwrap().to_owned(),
-            attempts_left: 12,
-        }
+        Wordle::new(&Settings::default())
     }
 }
 
 impl Wordle {
-    pub fn play() -> Option<()> {
-        let mut wordle = Wordle::default();
-        println!("Take a guess in this nice game of Wordle: ");
-        loop {
-            let mut line = String::new();
-            io::stdin().read_line(&mut line).expect("Could not read line");
-            line = line.trim().to_lowercase();
-            if line.len() != 5 {
-                println!("Guess a 5-letter word");
-                continue;
-            }
-            if line == "debug" {
-                break Some(());
+    /// Easier games draw everyday answers and allow more attempts
+    pub fn new(settings: &Settings) -> Self {
+        #[cfg(feature = "solver")]
+        if let Some(word) = crate::solver::answer() {
+            return Wordle::with_word(settings, word).expect("checked by solver::fix_answer");
+        }
+        Wordle::with_seed(settings, rand::rng().random())
+    }
+
+    /// The same answer for the same seed, settings and word list, so tests can replay a game
+    pub fn with_seed(settings: &Settings, seed: u64) -> Self {
+        let dictionary = dictionary();
+        let answers = match settings.difficulty {
+            Difficulty::Easy | Difficulty::Normal => &dictionary.common,
+            Difficulty::Hard => &dictionary.all,
+        };
+        Wordle::with_answer(settings, answers.choose(&mut StdRng::seed_from_u64(seed)).unwrap())
+    }
+
+    /// A game with a known answer, it has to be in the word list. For tests and `--wordle-answer`
+    #[cfg(any(test, feature = "solver"))]
+    pub fn with_word(settings: &Settings, word: &str) -> Result<Self> {
+        let word = normalize(word);
+        let dictionary = dictionary();
+        let index = dictionary
+            .allowed
+            .binary_search(&word)
+            .map_err(|_| anyhow::anyhow!("{word} is not in the word list"))?;
+        Ok(Wordle::with_answer(settings, &dictionary.allowed[index]))
+    }
+
+    fn with_answer(settings: &Settings, word: &'static str) -> Self {
+        let attempts_left = match settings.difficulty {
+            Difficulty::Easy => 12,
+            Difficulty::Normal => 8,
+            Difficulty::Hard => 6,
+        };
+        Wordle { word, attempts_left, hard_mode: settings.hard_mode, greens: [None; 5], revealed: Vec::new() }
+    }
+
+    /// Full screen board when on a terminal, lines on stderr otherwise since stdout may carry received data
+    pub async fn play(settings: &Settings, progress: TransferProgress, spectators: &Guesses) -> Outcome {
+        #[cfg(feature = "solver")]
+        if crate::solver::enabled() {
+            return crate::solver::play(settings, spectators);
+        }
+        let mut wordle = Wordle::new(settings);
+        let attempts = wordle.attempts_left as usize;
+        let mut screen = tui::available().then(|| tui::Screen::open_wordle(attempts)).and_then(Result::ok);
+        if screen.is_none() {
+            eprintln!("Take a guess in this nice game of Wordle, you have {attempts} attempts: ");
+            if wordle.hard_mode {
+                eprintln!("Hard mode: every revealed hint must be used in later guesses");
             }
-            let guess_result = wordle.guess(line.clone());
-            match guess_result {
-                Win => {
-                    println!("You win!");
-                    break Some(());
-                }
-                Loss => {
-                    println!("No guesses left");
-                    break None;
+        }
+        let cyrillic = wordle.word.chars().any(|letter| matches!(letter, 'а'..='я'));
+        let timeout = settings.answer_timeout.map(Duration::from_secs);
+        let mut guesses = Vec::new();
+        let mut message = format!("You have {attempts} attempts");
+        let (outcome, verdict) = loop {
+            if let Some(board) = screen.as_mut() {
+                let view = tui::WordleView {
+                    guesses: &guesses,
+                    attempts,
+                    cyrillic,
+                    hard_mode: wordle.hard_mode,
+                    message: &message,
+                    progress,
+                };
+                if let Err(e) = board.draw_wordle(&view) {
+                    warn!("Can not draw the board, falling back to line mode: {e}");
+                    screen = None;
                 }
-                TryAgain { result } => {
-                    for i in 0..result.len() {
-                        match result[i] {
-                            GRAY => {
-                                print!("{}", line.chars().nth(i).unwrap());
-                            }
-                            YELLOW => {
-                                print!("{}", String::from(line.chars().nth(i).unwrap()).yellow());
-                            }
-                            GREEN => {
-                                print!("{}", String::from(line.chars().nth(i).unwrap()).green());
+            }
+            let line = match crate::input::read_line(timeout).await {
+                Ok(Some(line)) => normalize(line.trim()),
+                Ok(None) => break (Outcome::Abort, None),
+                Err(_) => break (Outcome::Fail, Some("Time is up")),
+            };
+            message = if line.chars().count() != WORD_LENGTH {
+                "Guess a 5-letter word".to_owned()
+            } else {
+                match wordle.guess(&line) {
+                    Invalid => format!("{line} is not in the word list"),
+                    MissedHint { reason } => reason,
+                    Win => {
+                        let _ = spectators.send([Green; WORD_LENGTH]);
+                        break (Outcome::Pass, Some("You win!"));
+                    }
+                    Loss { result } => {
+                        if let Some(result) = result {
+                            if screen.is_none() {
+                                print_scored(&line, &result);
+                                eprint!(" ");
                             }
+                            let _ = spectators.send(result);
+                        }
+                        break (Outcome::Fail, Some("No guesses left"));
+                    }
+                    TryAgain { result } => {
+                        if screen.is_none() {
+                            print_scored(&line, &result);
+                            eprint!(" ");
                         }
+                        let _ = spectators.send(result);
+                        guesses.push((line, result));
+                        format!("You have {} more guesses", wordle.attempts_left)
                     }
-                    println!(" You have {} more guesses", wordle.attempts_left);
                 }
+            };
+            if screen.is_none() {
+                eprintln!("{message}");
+            }
+        };
+        // Back to the normal screen before the verdict, so it stays visible
+        drop(screen);
+        if let Some(verdict) = verdict {
+            eprintln!("{verdict}");
+        }
+        if outcome != Outcome::Abort {
+            // The winning guess is not among the scored ones
+            let won = (outcome == Outcome::Pass).then_some(guesses.len() + 1);
+            match Stats::record_game(won) {
+                Ok(stats) => eprintln!("{stats}"),
+                Err(e) => warn!("Can not update Wordle statistics: {e:#}"),
             }
         }
+        outcome
     }
 
-    pub fn guess(&mut self, guess: String) -> GuessResult {
+    /// Words that are not in the dictionary or ignore hints in hard mode do not cost an attempt
+    pub fn guess(&mut self, guess: &str) -> GuessResult {
+        let guess = normalize(guess);
         if self.attempts_left == 0 {
-            return Loss;
+            return Loss { result: None };
         };
+        if !dictionary().is_allowed(&guess) {
+            return Invalid;
+        }
+        if let Some(reason) = self.missed_hint(&guess) {
+            return MissedHint { reason };
+        }
         self.attempts_left -= 1;
         if guess == self.word {
             return Win;
         }
+        let result = score(self.word, &guess);
         if self.attempts_left == 0 {
-            return Loss;
+            return Loss { result: Some(result) };
         }
-        TryAgain {
-            result: [
-                self.guess_letter(guess.chars().nth(0).unwrap(), 0),
-                self.guess_letter(guess.chars().nth(1).unwrap(), 1),
-                self.guess_letter(guess.chars().nth(2).unwrap(), 2),
-                self.guess_letter(guess.chars().nth(3).unwrap(), 3),
-                self.guess_letter(guess.chars().nth(4).unwrap(), 4),
-            ],
+        if self.hard_mode {
+            self.remember_hints(&guess, &result);
         }
+        TryAgain { result }
     }
 
-    fn guess_letter(&self, letter: char, pos: usize) -> GuessLetterResult {
-        if letter.eq_ignore_ascii_case(&self.word.chars().nth(pos).unwrap()) {
-            return GREEN;
-        };
-        for i in 0..5 {
-            if letter.eq_ignore_ascii_case(&self.word.chars().nth(i).unwrap()) {
-                return YELLOW;
+    /// Why `guess` breaks hard mode, if it does
+    fn missed_hint(&self, guess: &str) -> Option<String> {
+        let guess: Vec<char> = guess.chars().collect();
+        for (i, green) in self.greens.iter().enumerate() {
+            if let Some(letter) = *green
+                && guess[i] != letter
+            {
+                return Some(format!("Letter {} must be {}", i + 1, letter.to_uppercase()));
+            }
+        }
+        for &(letter, count) in &self.revealed {
+            if guess.iter().filter(|&&c| c == letter).count() < count {
+                return Some(format!("Guess must contain {}", letter.to_uppercase()));
+            }
+        }
+        None
+    }
+
+    fn remember_hints(&mut self, guess: &str, result: &[GuessLetterResult; 5]) {
+        let mut found: Vec<(char, usize)> = Vec::new();
+        for (i, (letter, &letter_result)) in guess.chars().zip(result).enumerate() {
+            if letter_result == Green {
+                self.greens[i] = Some(letter);
+            }
+            if letter_result == Gray {
+                continue;
+            }
+            match found.iter_mut().find(|(known, _)| *known == letter) {
+                Some((_, count)) => *count += 1,
+                None => found.push((letter, 1)),
+            }
+        }
+        for (letter, count) in found {
+            match self.revealed.iter_mut().find(|(known, _)| *known == letter) {
+                Some((_, known_count)) => *known_count = (*known_count).max(count),
+                None => self.revealed.push((letter, count)),
             }
         }
-        GRAY
     }
 }
 
-enum GuessResult {
+/// A scored guess for line mode, coloured like the board
+fn print_scored(guess: &str, result: &[GuessLetterResult; 5]) {
+    for (letter, letter_result) in guess.chars().zip(result) {
+        match letter_result {
+            Gray => {
+                eprint!("{}", letter);
+            }
+            Yellow => {
+                eprint!("{}", String::from(letter).yellow());
+            }
+            Green => {
+                eprint!("{}", String::from(letter).green());
+            }
+        }
+    }
+}
+
+/// Score like real Wordle: exact matches first, then each remaining letter
+/// of the word can turn at most one misplaced guess letter yellow
+pub(crate) fn score(word: &str, guess: &str) -> [GuessLetterResult; 5] {
+    let word: Vec<char> = word.chars().collect();
+    let guess: Vec<char> = guess.chars().collect();
+    let mut result = [Gray; 5];
+    let mut unmatched = Vec::new();
+    for i in 0..5 {
+        if guess[i] == word[i] {
+            result[i] = Green;
+        } else {
+            unmatched.push(word[i]);
+        }
+    }
+    for i in 0..5 {
+        if result[i] == Green {
+            continue;
+        }
+        if let Some(pos) = unmatched.iter().position(|&letter| letter == guess[i]) {
+            unmatched.swap_remove(pos);
+            result[i] = Yellow;
+        }
+    }
+    result
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GuessResult {
     Win,
-    Loss,
+    /// Out of attempts, with the score of the last guess unless the game was over already
+    Loss { result: Option<[GuessLetterResult; 5]> },
     TryAgain { result: [GuessLetterResult; 5] },
+    /// Not a word, the attempt does not count
+    Invalid,
+    /// Hard mode guess that ignores a revealed hint, does not count either
+    MissedHint { reason: String },
 }
 
-enum GuessLetterResult {
-    GRAY,
-    YELLOW,
-    GREEN,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GuessLetterResult {
+    Gray,
+    Yellow,
+    Green,
 }
 
-const WORDS: [&str; 2477] = [
+pub(crate) static WORDS: [&str; 2477] = [
     "rossa", "jetty", "wizzo", "cuppa", "cohoe", "gurks", "squad", "beisa", "shrug", "fossa",
     "fluyt", "camus", "speed", "mamil", "array", "polio", "barns", "panes", "souts", "limas",
     "fetch", "queck", "twink", "graze", "crock", "almud", "oohed", "colog", "wisht", "beard",
@@ -360,3 +627,449 @@ const WORDS: [&str; 2477] = [
     "bahut", "hares", "joles", "kelpy", "triad", "poche", "mocap", "swami", "tocky", "femal",
     "goary", "waacs", "dunny", "decal", "fungs", "cadgy", "jalap",
 ];
+
+/// Everyday words, answers below hard difficulty
+static COMMON_WORDS: [&str; 1850] = [
+    "abbey", "about", "above", "abuse", "acorn", "acrid", "actor", "acute", "adapt", "admit",
+    "adopt", "adore", "adult", "after", "again", "agent", "aglow", "agree", "ahead", "aisle",
+    "alarm", "album", "alert", "alien", "alike", "alive", "alley", "allow", "aloft", "alone",
+    "along", "aloud", "alpha", "alter", "amber", "amble", "amend", "among", "ample", "angel",
+    "anger", "angle", "angry", "ankle", "annex", "antic", "anvil", "apart", "apple", "apply",
+    "april", "apron", "arbor", "ardor", "arena", "argue", "arise", "armed", "armor", "aroma",
+    "arose", "array", "arrow", "aside", "askew", "asset", "atlas", "attic", "audio", "audit",
+    "avert", "avoid", "awake", "award", "aware", "awful", "axiom", "azure", "bacon", "badge",
+    "badly", "bagel", "baggy", "baker", "banjo", "barge", "baron", "bases", "basic", "basin",
+    "basis", "batch", "bathe", "baton", "bayou", "beach", "beady", "beast", "beefy", "began",
+    "begin", "begun", "beige", "being", "belly", "below", "bench", "bends", "berry", "bible",
+    "bicep", "bilge", "bingo", "birch", "birth", "bison", "black", "blade", "blame", "bland",
+    "blank", "blast", "blaze", "bleak", "bleed", "blend", "bless", "blimp", "blind", "bliss",
+    "bloat", "block", "blond", "blood", "bloom", "blown", "bluff", "blunt", "blurb", "blurt",
+    "blush", "board", "boast", "bogus", "bonus", "boost", "booth", "bossy", "botch", "bough",
+    "bound", "boxer", "brace", "braid", "brain", "brake", "brand", "brass", "brave", "brawl",
+    "brawn", "bread", "break", "breed", "bribe", "brick", "bride", "brief", "brine", "bring",
+    "brink", "brisk", "broad", "broil", "broke", "brood", "brook", "broom", "broth", "brown",
+    "brush", "brute", "buddy", "budge", "buggy", "bugle", "build", "built", "bulge", "bulky",
+    "bully", "bunch", "bunny", "burly", "burnt", "burst", "bushy", "buyer", "cabin", "cable",
+    "cacao", "cadet", "camel", "cameo", "canal", "candy", "canoe", "caper", "cargo", "carol",
+    "carry", "carve", "caste", "catch", "cause", "cedar", "chain", "chair", "chalk", "champ",
+    "chant", "chaos", "charm", "chart", "chase", "chasm", "cheap", "check", "cheek", "cheer",
+    "chess", "chest", "chick", "chide", "chief", "child", "chili", "chill", "chime", "chirp",
+    "choir", "choke", "chord", "chore", "chose", "chunk", "churn", "cider", "cigar", "cinch",
+    "circa", "civic", "civil", "claim", "clamp", "clang", "clash", "clasp", "class", "claws",
+    "clean", "clear", "clerk", "click", "cliff", "climb", "cling", "cloak", "clock", "clone",
+    "close", "cloth", "cloud", "clown", "cluck", "clump", "clung", "coach", "coast", "color",
+    "comic", "coral", "corny", "couch", "cough", "could", "count", "coupe", "court", "cover",
+    "crack", "craft", "crane", "crank", "crash", "crate", "crave", "crawl", "craze", "crazy",
+    "creak", "cream", "creed", "creek", "creep", "crest", "crime", "crisp", "croak", "crook",
+    "cross", "crowd", "crown", "cruel", "crumb", "crush", "crust", "crypt", "cubic", "cumin",
+    "curly", "curry", "curse", "curve", "cycle", "daddy", "daily", "dairy", "daisy", "dance",
+    "dated", "datum", "dealt", "death", "debut", "decal", "decay", "decoy", "deity", "delay",
+    "delta", "delve", "denim", "dense", "depot", "depth", "derby", "detox", "devil", "diary",
+    "digit", "diner", "dingy", "dirty", "disco", "ditch", "ditto", "diver", "dizzy", "dodge",
+    "dogma", "doing", "dolly", "donor", "donut", "doubt", "dowdy", "dowel", "dowry", "dozen",
+    "draft", "drama", "drawn", "dread", "dream", "dress", "dried", "drift", "drill", "drink",
+    "drive", "droll", "drone", "drool", "droop", "dross", "drove", "drown", "dryer", "dunce",
+    "dusty", "dutch", "dwarf", "dwell", "dying", "eager", "eagle", "early", "earth", "easel",
+    "eaten", "ebony", "edict", "eerie", "eight", "eject", "elbow", "elder", "elect", "elite",
+    "elope", "elude", "email", "ember", "emcee", "empty", "enact", "endow", "enemy", "enjoy",
+    "enter", "entry", "envoy", "epoch", "epoxy", "equal", "equip", "erase", "erode", "error",
+    "erupt", "essay", "ethic", "evade", "event", "every", "evict", "evoke", "exact", "exalt",
+    "excel", "exert", "exile", "exist", "expel", "extra", "fable", "facet", "faint", "fairy",
+    "faith", "false", "fancy", "farce", "fatal", "fault", "feast", "feign", "fence", "feral",
+    "ferry", "fetch", "fetus", "fever", "fiber", "field", "fiend", "fiery", "fifth", "fifty",
+    "fight", "filth", "final", "finch", "first", "fixed", "flair", "flake", "flame", "flank",
+    "flare", "flash", "flask", "fleck", "fleet", "flesh", "flick", "flier", "fling", "flint",
+    "flirt", "float", "flock", "flood", "floor", "flora", "flour", "flown", "fluff", "fluid",
+    "fluke", "flung", "flush", "flute", "foamy", "focal", "focus", "foggy", "folly", "foray",
+    "force", "forge", "forgo", "forth", "forty", "forum", "found", "foyer", "frail", "frame",
+    "fraud", "freak", "fresh", "friar", "frill", "frisk", "frond", "front", "frost", "frown",
+    "froze", "fruit", "fudge", "fully", "fungi", "funky", "funny", "furry", "fussy", "fuzzy",
+    "gaily", "gamer", "gamut", "gaudy", "gauge", "gaunt", "gauze", "gavel", "gawky", "gecko",
+    "geese", "genie", "genre", "ghost", "ghoul", "giant", "giddy", "girth", "given", "gland",
+    "glare", "glass", "glaze", "gleam", "glean", "glide", "glint", "gloat", "globe", "gloom",
+    "glory", "gloss", "glove", "gnash", "gnome", "going", "goose", "gorge", "gouge", "gourd",
+    "grace", "grade", "grain", "grand", "grant", "grape", "graph", "grasp", "grass", "grate",
+    "grave", "gravy", "graze", "great", "greed", "green", "greet", "grief", "grill", "grime",
+    "grimy", "grind", "gripe", "groan", "groin", "groom", "grope", "gross", "group", "grout",
+    "growl", "grown", "gruel", "gruff", "grunt", "guano", "guard", "guess", "guest", "guide",
+    "guild", "guile", "guilt", "guise", "gulch", "gully", "gumbo", "gummy", "guppy", "gusto",
+    "gusty", "habit", "hairy", "halve", "handy", "happy", "hardy", "harem", "harsh", "haste",
+    "hasty", "hatch", "haunt", "haven", "havoc", "hazel", "heady", "heard", "heart", "heath",
+    "heave", "heavy", "hedge", "hefty", "heist", "hello", "hence", "hinge", "hippo", "hitch",
+    "hoard", "hobby", "hoist", "holly", "honey", "honor", "horde", "horse", "hotel", "hound",
+    "house", "hover", "howdy", "human", "humid", "humor", "humph", "humus", "hunch", "hurry",
+    "husky", "hutch", "hyena", "hyper", "icing", "ideal", "idiom", "igloo", "image", "inane",
+    "incur", "index", "inept", "inert", "infer", "ingot", "inlay", "inlet", "inner", "input",
+    "irony", "issue", "ivory", "jaunt", "jazzy", "jeans", "jelly", "jerky", "jetty", "jewel",
+    "jiffy", "joint", "joker", "jolly", "joust", "judge", "juice", "juicy", "jumbo", "jumpy",
+    "juror", "kappa", "karma", "kayak", "kebab", "khaki", "kiosk", "kitty", "knack", "knave",
+    "knead", "kneel", "knelt", "knife", "knock", "knoll", "known", "koala", "krill", "label",
+    "labor", "lager", "lanky", "lapel", "lapse", "large", "laser", "latch", "later", "lathe",
+    "latin", "laugh", "layer", "leafy", "leaky", "leapt", "learn", "lease", "least", "leave",
+    "ledge", "leech", "leery", "lefty", "legal", "lemon", "lemur", "level", "libel", "liege",
+    "light", "lilac", "limbo", "limit", "linen", "liner", "lingo", "links", "lipid", "lithe",
+    "liver", "lives", "llama", "lobby", "local", "lodge", "lofty", "logic", "loopy", "loose",
+    "lorry", "louse", "lousy", "lover", "lower", "lowly", "loyal", "lucky", "lumen", "lumpy",
+    "lunar", "lunch", "lunge", "lurch", "lurid", "lying", "lyric", "macaw", "macho", "macro",
+    "madam", "mafia", "magic", "major", "maker", "mambo", "manga", "mange", "mango", "mangy",
+    "mania", "manic", "manor", "maple", "march", "marry", "marsh", "mason", "match", "mauve",
+    "maxim", "maybe", "mayor", "mealy", "meant", "meaty", "medal", "media", "melee", "melon",
+    "mercy", "merge", "merit", "merry", "messy", "metal", "midge", "midst", "might", "mimic",
+    "mince", "miner", "minim", "minor", "minty", "minus", "mirth", "miser", "missy", "mixed",
+    "mocha", "model", "modem", "mogul", "moist", "molar", "moldy", "money", "month", "moose",
+    "moral", "morph", "mossy", "motel", "motif", "motor", "motto", "moult", "mound", "mount",
+    "mourn", "mouse", "mousy", "mouth", "movie", "muddy", "mulch", "mummy", "munch", "mural",
+    "murky", "mushy", "music", "musky", "musty", "myrrh", "nadir", "naive", "nanny", "nasal",
+    "nasty", "natal", "naval", "navel", "needs", "needy", "neigh", "nerdy", "nerve", "never",
+    "newer", "newly", "nicer", "niche", "niece", "night", "ninja", "ninny", "ninth", "noble",
+    "nobly", "noise", "nomad", "noose", "north", "noted", "novel", "nudge", "nurse", "nutty",
+    "nylon", "nymph", "oaken", "obese", "occur", "ocean", "oddly", "offal", "offer", "often",
+    "olive", "ombre", "omega", "onion", "onset", "opera", "opium", "optic", "orbit", "order",
+    "organ", "other", "otter", "ought", "outdo", "outer", "ovary", "ovate", "overt", "ovine",
+    "owing", "owner", "oxide", "ozone", "paddy", "pagan", "paint", "panda", "panel", "panic",
+    "pansy", "papal", "paper", "parer", "parka", "parry", "party", "pasta", "paste", "pasty",
+    "patch", "patio", "patsy", "patty", "pause", "payee", "peace", "peach", "pearl", "pecan",
+    "pedal", "penal", "pence", "penne", "peony", "perch", "peril", "perky", "pesky", "pesto",
+    "petal", "petty", "phase", "phone", "phony", "photo", "piano", "picky", "piece", "piety",
+    "piggy", "pilot", "pinch", "piney", "pinky", "pinto", "piper", "pique", "pitch", "pixel",
+    "pixie", "pizza", "place", "plaid", "plain", "plait", "plane", "plank", "plant", "plate",
+    "plaza", "plead", "pleat", "plied", "plier", "pluck", "plumb", "plume", "plump", "plunk",
+    "plush", "poesy", "point", "poise", "poker", "polar", "polka", "polyp", "pooch", "poppy",
+    "porch", "poser", "posse", "pouch", "pound", "pouty", "power", "prank", "prawn", "preen",
+    "press", "price", "prick", "pride", "pried", "prime", "primo", "print", "prior", "prism",
+    "privy", "prize", "probe", "prone", "prong", "proof", "prose", "proud", "prove", "prowl",
+    "proxy", "prude", "prune", "psalm", "pudgy", "puffy", "pulpy", "pulse", "punch", "pupil",
+    "puppy", "puree", "purge", "purse", "pushy", "putty", "quack", "quail", "quake", "qualm",
+    "quart", "quash", "quasi", "queen", "quell", "query", "quest", "queue", "quick", "quiet",
+    "quill", "quilt", "quirk", "quite", "quota", "quote", "rabbi", "rabid", "racer", "radar",
+    "radii", "radio", "rainy", "raise", "rajah", "rally", "ramen", "ranch", "range", "rapid",
+    "rarer", "raspy", "ratio", "raven", "rayon", "razor", "reach", "ready", "rebar", "rebel",
+    "rebus", "rebut", "recap", "recur", "recut", "reedy", "refer", "refit", "regal", "rehab",
+    "reign", "relax", "relay", "relic", "remit", "renal", "renew", "repay", "repel", "reply",
+    "rerun", "reset", "resin", "retch", "retro", "retry", "reuse", "revel", "revue", "rhino",
+    "rhyme", "rider", "ridge", "rifle", "right", "rigid", "rigor", "rinse", "ripen", "riper",
+    "risen", "riser", "risky", "rival", "river", "roast", "robot", "rocky", "rodeo", "rogue",
+    "roman", "roomy", "roost", "rotor", "rouge", "rough", "round", "route", "rowdy", "rower",
+    "royal", "ruddy", "ruder", "rugby", "ruler", "rumba", "rumor", "rupee", "rural", "rusty",
+    "sadly", "saint", "salad", "sally", "salon", "salsa", "salty", "salve", "salvo", "sandy",
+    "saner", "sappy", "sassy", "satin", "satyr", "sauce", "saucy", "sauna", "saute", "savor",
+    "savvy", "scald", "scale", "scalp", "scaly", "scamp", "scant", "scare", "scarf", "scary",
+    "scene", "scoff", "scold", "scone", "scoop", "scope", "score", "scorn", "scour", "scout",
+    "scowl", "scram", "scrap", "scree", "screw", "scrub", "scrum", "scuba", "sedan", "seedy",
+    "segue", "seize", "sense", "sepia", "serif", "serum", "serve", "setup", "seven", "sever",
+    "sewer", "shack", "shade", "shady", "shaft", "shake", "shaky", "shale", "shall", "shame",
+    "shank", "shape", "shard", "share", "sharp", "shawl", "shear", "sheen", "sheep", "sheer",
+    "sheet", "sheik", "shelf", "shell", "shied", "shift", "shine", "shiny", "shire", "shirk",
+    "shirt", "shoal", "shock", "shone", "shook", "shoot", "shore", "shorn", "short", "shout",
+    "shove", "shown", "showy", "shrew", "shrub", "shrug", "shuck", "shunt", "shush", "shyly",
+    "siege", "sieve", "sight", "sigma", "silky", "silly", "since", "sinew", "singe", "siren",
+    "sixth", "sixty", "sized", "skate", "skier", "skiff", "skill", "skimp", "skirt", "skulk",
+    "skull", "skunk", "slack", "slain", "slang", "slant", "slash", "slate", "slave", "sleek",
+    "sleep", "sleet", "slept", "slice", "slick", "slide", "slime", "slimy", "sling", "slink",
+    "sloop", "slope", "slosh", "sloth", "slump", "slung", "slunk", "slurp", "slush", "slyly",
+    "smack", "small", "smart", "smash", "smear", "smell", "smelt", "smile", "smirk", "smite",
+    "smith", "smock", "smoke", "smoky", "snack", "snail", "snake", "snaky", "snare", "snarl",
+    "sneak", "sneer", "snide", "sniff", "snipe", "snoop", "snore", "snort", "snout", "snowy",
+    "snuck", "snuff", "soapy", "sober", "soggy", "solar", "solid", "solve", "sonar", "sonic",
+    "sooth", "sooty", "sorry", "sound", "soupy", "south", "space", "spade", "spank", "spare",
+    "spasm", "spawn", "speak", "spear", "speck", "speed", "spell", "spend", "spent", "spice",
+    "spicy", "spied", "spiel", "spike", "spiky", "spill", "spilt", "spine", "spiny", "spire",
+    "spite", "splat", "split", "spoil", "spoke", "spoof", "spook", "spool", "spoon", "spore",
+    "sport", "spout", "spray", "spree", "sprig", "spunk", "spurn", "spurt", "squad", "squat",
+    "squib", "stack", "staff", "stage", "stain", "stair", "stake", "stalk", "stall", "stamp",
+    "stand", "stank", "stare", "stark", "start", "stash", "state", "stave", "stead", "steak",
+    "steal", "steam", "steed", "steel", "steep", "steer", "stein", "stern", "stick", "stiff",
+    "still", "sting", "stink", "stint", "stock", "stoic", "stoke", "stole", "stomp", "stone",
+    "stony", "stood", "stool", "stoop", "store", "storm", "story", "stout", "stove", "strap",
+    "straw", "stray", "strip", "strut", "stuck", "study", "stuff", "stung", "stunk", "stunt",
+    "style", "suave", "sugar", "suite", "sulky", "sully", "sumac", "sunny", "super", "surer",
+    "surge", "surly", "sushi", "swami", "swamp", "swarm", "swash", "swath", "swear", "sweat",
+    "sweep", "sweet", "swell", "swept", "swift", "swill", "swine", "swing", "swirl", "swish",
+    "swoon", "swoop", "sword", "swore", "sworn", "swung", "synod", "syrup", "tabby", "table",
+    "taboo", "tacit", "tacky", "taffy", "taint", "taken", "tally", "talon", "tamer", "tango",
+    "tangy", "taper", "tapir", "tardy", "tarot", "taste", "tasty", "tatty", "taunt", "tawny",
+    "taxes", "teach", "teary", "tease", "teddy", "teeny", "teeth", "tempo", "tenet", "tenor",
+    "tense", "tenth", "tepee", "tepid", "terse", "testy", "thank", "theft", "their", "theme",
+    "there", "these", "thick", "thief", "thigh", "thing", "think", "third", "thong", "thorn",
+    "those", "three", "threw", "throw", "thumb", "thump", "thyme", "tiara", "tibia", "tidal",
+    "tiger", "tight", "tilde", "timer", "times", "timid", "tipsy", "tired", "titan", "tithe",
+    "title", "toast", "today", "toddy", "token", "tonal", "tonic", "tooth", "topaz", "topic",
+    "torch", "torso", "total", "totem", "touch", "tough", "tower", "toxic", "toxin", "trace",
+    "track", "tract", "trade", "trail", "train", "trait", "tramp", "trash", "trawl", "tread",
+    "treat", "trend", "triad", "trial", "tribe", "trice", "trick", "tried", "tries", "trite",
+    "troll", "troop", "trope", "trout", "trove", "truce", "truck", "truly", "trump", "trunk",
+    "truss", "trust", "truth", "tryst", "tubal", "tuber", "tulip", "tulle", "tumor", "tunic",
+    "turbo", "tutor", "twang", "tweak", "tweed", "tweet", "twice", "twine", "twirl", "twist",
+    "udder", "ulcer", "ultra", "umbra", "uncle", "uncut", "under", "undid", "undue", "unfed",
+    "unfit", "unify", "union", "unity", "unlit", "untie", "until", "unwed", "unzip", "upper",
+    "upset", "urban", "usage", "usher", "usual", "usurp", "utter", "vague", "valet", "valid",
+    "valor", "value", "valve", "vapid", "vapor", "vault", "vaunt", "vegan", "venom", "venue",
+    "verge", "verse", "verso", "verve", "vicar", "video", "vigil", "vigor", "villa", "vinyl",
+    "viola", "viper", "viral", "virus", "visit", "visor", "vista", "vital", "vivid", "vixen",
+    "vocal", "vodka", "vogue", "voice", "voila", "vomit", "voter", "vouch", "vowel", "wacky",
+    "wafer", "wager", "wagon", "waist", "waive", "waltz", "warty", "waste", "watch", "water",
+    "weary", "weave", "wedge", "weedy", "weigh", "weird", "whack", "whale", "wharf", "wheat",
+    "wheel", "whelp", "where", "which", "whiff", "while", "whine", "whiny", "whirl", "whisk",
+    "white", "whole", "whoop", "whose", "widen", "widow", "width", "wield", "wight", "wimpy",
+    "wince", "winch", "windy", "wiser", "wispy", "witch", "witty", "woken", "woman", "women",
+    "wooer", "wordy", "world", "worry", "worse", "worst", "worth", "would", "wound", "wrath",
+    "wreak", "wreck", "wrest", "wring", "wrist", "write", "wrong", "wrote", "wryly", "yacht",
+    "yearn", "yeast", "yield", "yodel", "young", "youth", "yummy", "zebra", "zesty", "zonal",
+];
+
+/// Accepted as guesses on top of the answer lists, kept apart so trimming `COMMON_WORDS` never rejects a guess
+static GUESSES: [&str; 1511] = [
+    "abbey", "about", "above", "abuse", "acrid", "actor", "acute", "admit", "adopt", "adore",
+    "adult", "after", "agent", "agree", "ahead", "aisle", "alarm", "album", "alert", "alien",
+    "alike", "alley", "allow", "aloft", "alone", "along", "aloud", "alpha", "alter", "amend",
+    "among", "ample", "angel", "anger", "angle", "angry", "annex", "antic", "anvil", "apart",
+    "apple", "april", "arbor", "arena", "argue", "arise", "armed", "armor", "aroma", "arose",
+    "arrow", "aside", "askew", "asset", "atlas", "attic", "audio", "audit", "avert", "avoid",
+    "awake", "award", "aware", "awful", "axiom", "azure", "bacon", "badly", "bagel", "baggy",
+    "baker", "banjo", "basin", "basis", "batch", "baton", "beach", "beady", "beast", "beefy",
+    "began", "beige", "being", "belly", "below", "bench", "bends", "berry", "bible", "bicep",
+    "bilge", "bingo", "birch", "bison", "black", "blade", "blame", "bland", "blast", "blaze",
+    "bleak", "bleed", "blend", "bless", "blimp", "bliss", "bloat", "block", "blond", "blood",
+    "bloom", "blown", "bluff", "blunt", "blurt", "blush", "boast", "bogus", "bonus", "boost",
+    "booth", "bossy", "botch", "bough", "bound", "boxer", "braid", "brain", "brake", "brand",
+    "brass", "brave", "brawl", "brawn", "bread", "break", "breed", "brick", "bride", "brief",
+    "brine", "bring", "brink", "brisk", "broad", "broke", "brood", "brook", "broom", "broth",
+    "brush", "brute", "buddy", "budge", "buggy", "bugle", "build", "built", "bulge", "bulky",
+    "bully", "bunch", "bunny", "burly", "burnt", "burst", "buyer", "cabin", "cable", "cacao",
+    "cadet", "camel", "cameo", "candy", "canoe", "caper", "cargo", "carol", "carry", "carve",
+    "caste", "catch", "cause", "cedar", "chain", "chalk", "champ", "chaos", "charm", "chart",
+    "chase", "chasm", "cheap", "check", "cheek", "cheer", "chess", "chide", "child", "chili",
+    "chill", "chime", "chirp", "choir", "choke", "chord", "chunk", "churn", "cigar", "circa",
+    "civic", "civil", "claim", "clamp", "clang", "clasp", "claws", "clean", "clear", "clerk",
+    "cling", "clock", "clone", "close", "cloth", "cloud", "clown", "cluck", "clump", "clung",
+    "coach", "coast", "color", "comic", "coral", "corny", "count", "coupe", "court", "cover",
+    "crack", "craft", "crane", "crash", "crave", "crawl", "craze", "crazy", "creak", "cream",
+    "creed", "creek", "creep", "crest", "crisp", "crook", "crowd", "crumb", "crush", "crust",
+    "crypt", "cubic", "cumin", "curly", "curry", "curse", "curve", "daddy", "daily", "dairy",
+    "daisy", "dated", "datum", "death", "decay", "decoy", "deity", "delay", "delve", "denim",
+    "depot", "depth", "derby", "detox", "diary", "digit", "dingy", "dirty", "disco", "ditch",
+    "ditto", "dizzy", "dodge", "dogma", "doing", "dolly", "donor", "donut", "dowdy", "dowel",
+    "dowry", "dozen", "draft", "dread", "dream", "dress", "dried", "drift", "drill", "drink",
+    "drone", "drool", "droop", "drown", "dryer", "dunce", "dusty", "dutch", "dwarf", "dwell",
+    "dying", "eagle", "early", "earth", "eaten", "ebony", "edict", "eerie", "eight", "eject",
+    "elder", "elect", "elite", "elope", "elude", "email", "emcee", "empty", "enact", "endow",
+    "enemy", "enjoy", "enter", "entry", "envoy", "epoch", "equip", "erase", "erode", "error",
+    "erupt", "essay", "evade", "event", "every", "evict", "exact", "exalt", "excel", "exert",
+    "exile", "exist", "expel", "extra", "fable", "facet", "fairy", "false", "fancy", "fatal",
+    "fault", "feast", "feign", "fence", "feral", "fetus", "fiber", "field", "fiend", "fiery",
+    "fifth", "fight", "final", "first", "fixed", "flair", "flake", "flame", "flank", "flare",
+    "flash", "flask", "fleck", "fleet", "flesh", "flick", "flier", "fling", "flint", "flirt",
+    "float", "flock", "flood", "floor", "flora", "flour", "fluff", "fluid", "fluke", "flush",
+    "flute", "focal", "foggy", "folly", "foray", "force", "forge", "forgo", "forth", "forty",
+    "forum", "found", "foyer", "frail", "fraud", "freak", "fresh", "friar", "frill", "frisk",
+    "frond", "front", "frost", "frown", "froze", "fudge", "fully", "fungi", "funky", "funny",
+    "furry", "fussy", "fuzzy", "gaily", "gamut", "gaudy", "gauge", "gaunt", "gauze", "gavel",
+    "gecko", "geese", "genie", "genre", "ghoul", "giant", "girth", "given", "gland", "glare",
+    "glass", "glaze", "gleam", "glean", "glide", "glint", "gloat", "gloom", "glory", "gloss",
+    "gnash", "gnome", "goose", "gorge", "gouge", "gourd", "grace", "grade", "grain", "grant",
+    "grape", "graph", "grasp", "grass", "grate", "grave", "gravy", "great", "greed", "green",
+    "greet", "grief", "grime", "gripe", "groan", "groin", "groom", "grope", "gross", "group",
+    "grout", "growl", "grown", "gruel", "gruff", "grunt", "guano", "guard", "guess", "guest",
+    "guile", "guilt", "guise", "gulch", "gully", "gumbo", "guppy", "gusto", "gusty", "habit",
+    "hairy", "halve", "handy", "hardy", "harem", "harsh", "haste", "hasty", "hatch", "haunt",
+    "haven", "havoc", "hazel", "heady", "heard", "heart", "heath", "heave", "heavy", "hedge",
+    "hefty", "heist", "hello", "hinge", "hippo", "hitch", "hoard", "hobby", "holly", "honey",
+    "honor", "horde", "horse", "hotel", "hound", "hover", "howdy", "human", "humid", "humor",
+    "humph", "humus", "hunch", "husky", "hutch", "hyper", "icing", "ideal", "idiom", "igloo",
+    "image", "inane", "incur", "index", "inept", "infer", "ingot", "inlay", "inlet", "inner",
+    "irony", "issue", "jaunt", "jazzy", "jeans", "jelly", "jerky", "jewel", "joint", "jolly",
+    "joust", "juice", "juicy", "juror", "kappa", "kayak", "kebab", "khaki", "knack", "knave",
+    "knead", "kneel", "knife", "knock", "knoll", "koala", "krill", "label", "labor", "lager",
+    "lapel", "lapse", "large", "laser", "latch", "later", "lathe", "latin", "laugh", "layer",
+    "leaky", "leapt", "lease", "least", "leave", "ledge", "leery", "legal", "lemon", "lemur",
+    "level", "libel", "liege", "light", "lilac", "limbo", "liner", "links", "lipid", "lithe",
+    "liver", "lives", "llama", "lobby", "local", "lodge", "lofty", "logic", "loopy", "loose",
+    "lorry", "louse", "lousy", "lower", "lowly", "lucky", "lumen", "lumpy", "lunar", "lunch",
+    "lunge", "lurid", "lying", "lyric", "macaw", "macho", "macro", "madam", "mafia", "major",
+    "maker", "mambo", "manga", "mange", "mango", "mangy", "mania", "manic", "manor", "march",
+    "mason", "match", "mauve", "maxim", "mayor", "mealy", "meant", "meaty", "medal", "media",
+    "melee", "melon", "merit", "merry", "messy", "metal", "midst", "mimic", "miner", "minim",
+    "minor", "minty", "minus", "mirth", "missy", "mixed", "mocha", "model", "modem", "moist",
+    "molar", "moldy", "money", "month", "moose", "moral", "morph", "mossy", "motif", "motto",
+    "moult", "mound", "mount", "mourn", "mouse", "mousy", "mouth", "movie", "muddy", "mulch",
+    "mummy", "munch", "mural", "mushy", "music", "musty", "myrrh", "nadir", "naive", "nanny",
+    "nasty", "natal", "naval", "navel", "needs", "needy", "neigh", "nerdy", "nerve", "newer",
+    "niche", "night", "noble", "nomad", "noose", "north", "noted", "novel", "nudge", "nutty",
+    "nylon", "oaken", "obese", "occur", "ocean", "oddly", "offal", "offer", "often", "olive",
+    "ombre", "omega", "onset", "opium", "optic", "orbit", "order", "other", "otter", "outer",
+    "ovary", "ovate", "overt", "ovine", "owing", "owner", "ozone", "paddy", "pagan", "paint",
+    "panel", "panic", "pansy", "papal", "paper", "parka", "parry", "party", "pasta", "paste",
+    "pasty", "patch", "patsy", "patty", "pause", "peace", "peach", "pecan", "pedal", "penal",
+    "pence", "penne", "peony", "perch", "perky", "pesky", "pesto", "petal", "petty", "phase",
+    "phone", "photo", "picky", "piece", "piggy", "pilot", "pinch", "piney", "pinky", "pinto",
+    "piper", "pique", "pixie", "pizza", "place", "plaid", "plain", "plait", "plane", "plank",
+    "plant", "plate", "plaza", "plead", "pleat", "plied", "plier", "pluck", "plumb", "plume",
+    "plump", "plunk", "point", "poise", "poker", "polar", "polka", "polyp", "poppy", "porch",
+    "poser", "posse", "pouch", "pound", "pouty", "power", "prank", "preen", "press", "price",
+    "prick", "pride", "pried", "prime", "primo", "print", "prior", "prism", "privy", "probe",
+    "prone", "prong", "proof", "prose", "proud", "prove", "prowl", "proxy", "prude", "prune",
+    "psalm", "pudgy", "puffy", "pulse", "pupil", "puppy", "puree", "purge", "purse", "pushy",
+    "putty", "quail", "quake", "qualm", "quart", "quash", "queen", "query", "quest", "queue",
+    "quick", "quiet", "quill", "quilt", "quirk", "quite", "quota", "quote", "rabbi", "rabid",
+    "racer", "radar", "radii", "radio", "raise", "rajah", "rally", "ramen", "ranch", "range",
+    "raspy", "raven", "rayon", "razor", "ready", "rebar", "rebel", "rebus", "rebut", "recut",
+    "reedy", "refer", "refit", "regal", "rehab", "reign", "relax", "relic", "remit", "renal",
+    "renew", "repay", "repel", "reply", "rerun", "reset", "resin", "retch", "retro", "reuse",
+    "revue", "rhino", "rhyme", "rider", "ridge", "rifle", "rigid", "rigor", "rinse", "ripen",
+    "riper", "riser", "risky", "river", "roast", "robot", "rocky", "rodeo", "rogue", "roomy",
+    "roost", "rotor", "rouge", "rough", "round", "route", "rowdy", "rower", "royal", "ruddy",
+    "ruder", "ruler", "rumba", "rupee", "rural", "rusty", "sadly", "saint", "salon", "salsa",
+    "salty", "salve", "sandy", "saner", "sappy", "sassy", "satin", "satyr", "sauce", "saucy",
+    "sauna", "saute", "savor", "savvy", "scald", "scale", "scaly", "scant", "scarf", "scary",
+    "scoff", "scold", "scone", "scoop", "scope", "score", "scorn", "scour", "scout", "scram",
+    "scree", "screw", "scrub", "scrum", "scuba", "sedan", "seedy", "segue", "seize", "sense",
+    "sepia", "serif", "serum", "serve", "setup", "seven", "sever", "shack", "shade", "shady",
+    "shaft", "shake", "shaky", "shale", "shall", "shame", "shank", "shape", "shard", "share",
+    "shawl", "shear", "sheen", "sheep", "sheer", "sheet", "sheik", "shelf", "shied", "shift",
+    "shiny", "shirk", "shoal", "shone", "shook", "shoot", "shorn", "shout", "shove", "shown",
+    "showy", "shrew", "shuck", "shunt", "shush", "shyly", "siege", "sieve", "sigma", "silky",
+    "silly", "since", "sinew", "singe", "siren", "sixth", "sized", "skate", "skiff", "skill",
+    "skimp", "skirt", "skulk", "skull", "slack", "slain", "slant", "slash", "slave", "sleek",
+    "sleep", "sleet", "slept", "slice", "slick", "slide", "slimy", "sling", "slink", "sloop",
+    "slosh", "sloth", "slung", "slush", "smack", "small", "smart", "smash", "smear", "smell",
+    "smile", "smirk", "smite", "smock", "smoke", "smoky", "snack", "snake", "snare", "sneak",
+    "sneer", "snide", "sniff", "snipe", "snore", "snort", "snout", "snuck", "snuff", "soapy",
+    "soggy", "solid", "solve", "sonar", "sonic", "sooty", "sorry", "soupy", "south", "space",
+    "spade", "spank", "spare", "spasm", "speak", "spear", "spell", "spent", "spice", "spicy",
+    "spied", "spiel", "spike", "spiky", "spilt", "spine", "spiny", "spire", "splat", "split",
+    "spoil", "spoof", "spook", "spool", "spoon", "spore", "spray", "spree", "sprig", "spunk",
+    "spurn", "spurt", "squat", "squib", "stack", "staff", "stage", "stain", "stair", "stake",
+    "stalk", "stall", "stamp", "stand", "stank", "stare", "stark", "start", "stash", "state",
+    "stave", "stead", "steak", "steal", "steel", "steep", "stein", "stern", "stick", "stiff",
+    "still", "sting", "stink", "stint", "stock", "stoic", "stoke", "stole", "stone", "stood",
+    "stool", "stoop", "store", "storm", "stout", "strap", "straw", "stray", "strip", "strut",
+    "stuck", "stuff", "stunk", "stunt", "suave", "sugar", "suite", "sulky", "sully", "sumac",
+    "sunny", "super", "surer", "surge", "surly", "sushi", "swamp", "swash", "swath", "sweat",
+    "sweep", "sweet", "swell", "swift", "swill", "swine", "swing", "swirl", "swoon", "sworn",
+    "synod", "syrup", "tabby", "table", "taboo", "tacit", "taint", "taken", "tally", "talon",
+    "tango", "tangy", "tapir", "tarot", "taste", "tasty", "tatty", "taunt", "taxes", "teach",
+    "teary", "teddy", "teeny", "teeth", "tempo", "tenet", "tenor", "tense", "tepee", "tepid",
+    "terse", "testy", "thank", "theft", "theme", "there", "these", "thick", "thief", "think",
+    "third", "thong", "those", "three", "threw", "throw", "thumb", "thump", "thyme", "tibia",
+    "tidal", "tight", "tilde", "timer", "times", "timid", "tipsy", "titan", "tithe", "title",
+    "toast", "today", "toddy", "tonal", "tooth", "topaz", "torch", "total", "totem", "touch",
+    "tough", "tower", "toxin", "trace", "tract", "trade", "trail", "train", "tramp", "trash",
+    "trawl", "treat", "trend", "trial", "tribe", "trice", "tried", "trite", "troll", "troop",
+    "trope", "trout", "trove", "truce", "truck", "truly", "trump", "trunk", "truss", "trust",
+    "truth", "tryst", "tubal", "tuber", "tulip", "tulle", "tumor", "turbo", "tutor", "twang",
+    "tweak", "tweed", "twice", "twine", "twirl", "twist", "udder", "ulcer", "ultra", "umbra",
+    "uncut", "under", "undid", "undue", "unfed", "unfit", "unify", "union", "unity", "unlit",
+    "untie", "until", "unwed", "unzip", "upset", "urban", "usher", "usual", "usurp", "utter",
+    "valet", "valid", "valor", "value", "vapid", "vapor", "vault", "vaunt", "vegan", "venom",
+    "venue", "verge", "verse", "verso", "verve", "vicar", "video", "vigil", "vigor", "villa",
+    "vinyl", "viola", "viper", "viral", "visit", "visor", "vista", "vital", "vivid", "vixen",
+    "vocal", "vodka", "vogue", "voice", "voila", "voter", "vowel", "wacky", "wafer", "wager",
+    "waive", "waltz", "warty", "water", "weary", "wedge", "weedy", "weigh", "weird", "whack",
+    "whale", "wharf", "wheel", "whelp", "where", "whiff", "while", "whine", "whiny", "whisk",
+    "white", "whole", "whoop", "whose", "widow", "width", "wield", "wight", "wimpy", "winch",
+    "wispy", "witch", "witty", "woken", "woman", "women", "wooer", "wordy", "world", "worry",
+    "worst", "worth", "would", "wound", "wreak", "wreck", "wrest", "wring", "wrist", "write",
+    "wrong", "wrote", "yacht", "yearn", "yeast", "yield", "young", "youth", "yummy", "zebra",
+    "zesty",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wordle(word: &'static str) -> Wordle {
+        Wordle::with_word(&Settings { difficulty: Difficulty::Easy, ..Settings::default() }, word).unwrap()
+    }
+
+    #[test]
+    fn repeated_guess_letter_beyond_word_count_is_gray() {
+        // Only one unmatched b is left in abbey, so the second stray b stays gray
+        assert_eq!(score("abbey", "bobby"), [Yellow, Gray, Green, Gray, Green]);
+    }
+
+    #[test]
+    fn greens_use_up_letters_before_yellows() {
+        assert_eq!(score("hello", "lolly"), [Gray, Yellow, Green, Green, Gray]);
+        assert_eq!(score("crane", "eerie"), [Gray, Gray, Yellow, Gray, Green]);
+    }
+
+    #[test]
+    fn repeated_word_letter_can_match_twice() {
+        assert_eq!(score("hello", "llama"), [Yellow, Yellow, Gray, Gray, Gray]);
+    }
+
+    #[test]
+    fn unknown_words_cost_nothing() {
+        let mut game = wordle("crane");
+        assert_eq!(game.guess("xqzvk"), Invalid);
+        assert_eq!(game.attempts_left, 12);
+        assert!(matches!(game.guess("eerie"), TryAgain { .. }));
+        assert_eq!(game.attempts_left, 11);
+        assert_eq!(game.guess("crane"), Win);
+    }
+
+    #[test]
+    fn hard_mode_requires_revealed_hints() {
+        let mut game = Wordle { hard_mode: true, ..wordle("crane") };
+        assert!(matches!(game.guess("eerie"), TryAgain { .. }));
+        // r was misplaced and the final e is in place
+        assert!(matches!(game.guess("about"), MissedHint { .. }));
+        assert!(matches!(game.guess("rinse"), TryAgain { .. }));
+        assert!(matches!(game.guess("horse"), MissedHint { .. }));
+        assert_eq!(game.attempts_left, 10);
+        assert_eq!(game.guess("crane"), Win);
+    }
+
+    #[test]
+    fn seeded_games_repeat() {
+        let settings = Settings::default();
+        let words: Vec<&str> = (0..20).map(|seed| Wordle::with_seed(&settings, seed).word).collect();
+        assert_eq!(words, (0..20).map(|seed| Wordle::with_seed(&settings, seed).word).collect::<Vec<_>>());
+        assert!(words.iter().any(|&word| word != words[0]));
+    }
+
+    #[test]
+    fn last_wrong_guess_is_scored() {
+        let mut game = Wordle::with_word(&Settings { difficulty: Difficulty::Hard, ..Settings::default() }, "crane").unwrap();
+        for _ in 1..6 {
+            assert!(matches!(game.guess("pious"), TryAgain { .. }));
+        }
+        assert_eq!(game.guess("trace"), Loss { result: Some(score("crane", "trace")) });
+        assert_eq!(game.guess("crane"), Loss { result: None });
+    }
+
+    #[test]
+    fn target_words_are_checked() {
+        let mut game = Wordle::with_word(&Settings::default(), "CRANE").unwrap();
+        assert_eq!(game.attempts_left, 8);
+        assert_eq!(game.guess("crane"), Win);
+        assert!(Wordle::with_word(&Settings::default(), "xqzvk").is_err());
+    }
+
+    #[test]
+    fn every_answer_can_be_guessed() {
+        let english = Dictionary::english();
+        assert!(english.all.iter().chain(&english.common).all(|word| english.is_allowed(word)));
+        assert!(GUESSES.iter().all(|word| english.is_allowed(word)));
+    }
+
+    #[test]
+    fn russian_list_is_valid() {
+        let russian = Dictionary::load("ru").unwrap();
+        assert!(russian.is_allowed("ветер"));
+        assert!(!russian.is_allowed("crane"));
+    }
+
+    #[test]
+    fn cyrillic_letters_score_by_character() {
+        assert_eq!(score("ветер", "вечер"), [Green, Green, Gray, Green, Green]);
+        assert_eq!(score("ветер", "тесто"), [Yellow, Green, Gray, Gray, Gray]);
+    }
+
+    #[test]
+    fn word_lists_are_checked() {
+        assert!(Dictionary::parse("# words\nКлЁст\n", "test").unwrap().is_allowed("клест"));
+        assert!(Dictionary::parse("ветер\nвесна\nсон\n", "test").is_err());
+        assert!(Dictionary::parse("# nothing\n", "test").is_err());
+    }
+}
@@ -5,6 +5,8 @@ import Form from 'react-bootstrap/Form'
 import ProgressBar from 'react-bootstrap/ProgressBar'
 import { BACKEND_BASE, STUN_SERVERS } from '../config'
 import { waitForIceGatheringComplete } from '../utils/webrtc'
+import { decodeFrame } from '../utils/frame'
+import { generateKeyPair, exportPublicKey, deriveSessionKey, openBytes, openText, sealText } from '../utils/crypto'
 import Game, { getRandomGameType } from './Game'
 
 export default function Receiver() {
@@ -12,6 +14,8 @@ export default function Receiver() {
     const pcRef = useRef(null)
     const dcRef = useRef(null)
     const incoming = useRef(null)
+    const keyRef = useRef(null)
+    const queueRef = useRef(Promise.resolve())
     const [state, setState] = useState('idle')
     const [progress, setProgress] = useState({ received: 0, total: null })
     const [downloadProgress, setDownloadProgress] = useState({ download: 0, total: 0 })
@@ -44,9 +48,20 @@ export default function Receiver() {
         closeConnection()
         resetIncoming()
 
+        // Codes of command line senders end in a secret that only their key exchange can use
+        if (id.trim().includes('-')) {
+            alert('This code is for the command line receiver. Ask the sender to use --web to send to a browser')
+            return
+        }
+
         setState('connecting')
-        const r = await fetch(`${BACKEND_BASE}/api/peer/${id}`)
+        const r = await fetch(`${BACKEND_BASE}/api/peer/${id.trim()}`)
         const j = await r.json()
+        if (j.peer && typeof j.peer.webRTC === 'string') {
+            alert('This offer is encrypted for the command line receiver. Ask the sender to use --web to send to a browser')
+            setState('idle')
+            return
+        }
         if (!j.peer || !j.peer.webRTC || !j.peer.webRTC.offer) {
             alert('No offer for this ID')
             setState('idle')
@@ -54,6 +69,10 @@ export default function Receiver() {
         }
         const offer = j.peer.webRTC.offer
 
+        const keyPair = await generateKeyPair()
+        const publicKey = await exportPublicKey(keyPair)
+        keyRef.current = await deriveSessionKey(keyPair, j.peer.publicKey, j.peer.publicKey, publicKey)
+
         const pc = new RTCPeerConnection({ iceServers: STUN_SERVERS })
         pcRef.current = pc
         pc.oniceconnectionstatechange = () => setState(pc.iceConnectionState)
@@ -62,7 +81,10 @@ export default function Receiver() {
             const dc = ev.channel
             dc.binaryType = 'arraybuffer'
             dc.onopen = () => setState('connected')
-            dc.onmessage = (e) => handleIncoming(e.data)
+            // Decryption is async, keep messages in order
+            dc.onmessage = (e) => {
+                queueRef.current = queueRef.current.then(() => handleIncoming(e.data)).catch(console.error)
+            }
             dcRef.current = dc
         }
 
@@ -71,29 +93,30 @@ export default function Receiver() {
         await pc.setLocalDescription(answer)
         await waitForIceGatheringComplete(pc)
 
-        // TODO: implement publicKey exchange
-        await fetch(`${BACKEND_BASE}/api/peer/${id}/client`, {
+        await fetch(`${BACKEND_BASE}/api/peer/${id.trim()}/client`, {
             method: 'POST', headers: { 'Content-Type': 'application/json' },
-            body: JSON.stringify({ client: { publicKey: 'TODO', webRTC: { answer: pc.localDescription } } })
+            body: JSON.stringify({ client: { publicKey, webRTC: { answer: pc.localDescription } } })
         })
 
         setState('answer_posted')
     }
 
     function initIncoming(name, size) {
-        incoming.current = { name: name ?? null, size: size ?? null, chunks: [], received: 0, endReceived: false }
+        incoming.current = { name: name ?? null, size: size ?? null, chunks: [], received: 0, endReceived: false, nextSequence: 0 }
         setProgress({ received: 0, total: size ?? null, name: name ?? '' })
         setDownloadProgress({ download: 0, total: size ?? null })
         downloadedChunks.current = { chunks: 0, chunksSize: 0 }
         gameType.current = getRandomGameType()
     }
 
-    function handleIncoming(data) {
+    async function handleIncoming(data) {
         if (typeof data === 'string') {
             try {
-                const meta = JSON.parse(data)
+                const meta = JSON.parse(await openText(data, keyRef.current))
                 if (meta.type == "begin") {
                     initIncoming(meta.fileInfo?.fileName ?? null, meta.fileInfo?.fileSize ?? null)
+                    // Browser downloads can't be continued, always start from scratch
+                    if (meta.transferId) dcRef.current?.send(await sealText(JSON.stringify({ type: "resume", offset: 0 }), keyRef.current))
                 } else if (meta.type == "end") {
                     if (!incoming.current) initIncoming(null, null)
                     incoming.current.endReceived = true
@@ -110,7 +133,19 @@ export default function Receiver() {
                 console.warn('string message', data)
             }
         } else {
-            const arr = new Uint8Array(data)
+            const frame = decodeFrame(await openBytes(data, keyRef.current))
+            if (frame.sequence < incoming.current.nextSequence) {
+                console.warn('duplicate frame', frame.sequence)
+                return
+            }
+            if (frame.sequence !== incoming.current.nextSequence || frame.offset !== incoming.current.received) {
+                console.error('out of order frame', frame.sequence, frame.offset)
+                closeConnection()
+                setState('failed')
+                return
+            }
+            incoming.current.nextSequence += 1
+            const arr = frame.payload
             incoming.current.chunks.push(arr)
             incoming.current.received += arr.byteLength
             setProgress({ received: incoming.current.received, total: incoming.current.size ?? null, name: incoming.current.name ?? '' })
@@ -5,6 +5,8 @@ import Form from 'react-bootstrap/Form'
 import ProgressBar from 'react-bootstrap/ProgressBar'
 import { BACKEND_BASE, STUN_SERVERS, CHUNK_SIZE } from '../config'
 import { waitForIceGatheringComplete, sleep } from '../utils/webrtc'
+import { encodeFrame } from '../utils/frame'
+import { generateKeyPair, exportPublicKey, deriveSessionKey, sealBytes, sealText } from '../utils/crypto'
 import Game, { getRandomGameType, getGameActionDescription } from './Game'
 
 const BUFFER_SIZE_LOW = CHUNK_SIZE * 4
@@ -15,6 +17,9 @@ export default function Transmitter() {
     const dcRef = useRef(null)
     const pollRef = useRef(null)
     const chunksRef = useRef(null)
+    const keyRef = useRef(null)
+    // Sealing is async, so sends are chained to keep frame numbers unique and in order
+    const sendQueueRef = useRef(Promise.resolve())
     const [file, setFile] = useState(null)
 
     const [state, setState] = useState('idle')
@@ -42,8 +47,9 @@ export default function Transmitter() {
         await pc.setLocalDescription(offer)
         await waitForIceGatheringComplete(pc)
 
-        // TODO: implement publicKey exchange
-        const body = { peer: { publicKey: 'TODO', webRTC: { offer: pc.localDescription } } }
+        const keyPair = await generateKeyPair()
+        const publicKey = await exportPublicKey(keyPair)
+        const body = { peer: { publicKey, webRTC: { offer: pc.localDescription } } }
         const resp = await fetch(`${BACKEND_BASE}/api/peer`, {
             method: 'POST', headers: { 'Content-Type': 'application/json' }, body: JSON.stringify(body)
         })
@@ -57,6 +63,7 @@ export default function Transmitter() {
                 const p = await r.json()
                 if (p.client && p.client.webRTC && p.client.webRTC.answer) {
                     clearInterval(pollRef.current)
+                    keyRef.current = await deriveSessionKey(keyPair, p.client.publicKey, publicKey, p.client.publicKey)
                     const answer = p.client.webRTC.answer
                     await pc.setRemoteDescription(answer)
                     setState('connected')
@@ -96,18 +103,25 @@ export default function Transmitter() {
         setProgress({ sent: 0, total: total })
         gameType.current = getRandomGameType()
 
-        dcRef.current.send(JSON.stringify({
+        dcRef.current.send(await sealText(JSON.stringify({
             type: "begin",
             fileInfo: { fileName: file.name, fileSize: total }
-        }))
+        }), keyRef.current))
 
         setState('ready')
 
         dcRef.current.bufferedAmountLowThreshold = BUFFER_SIZE_LOW;
         dcRef.current.onbufferedamountlow = async (ev) => {
-            await sendWhileBufferedUnderThreshold()
+            await queueSending()
         };
-        await sendWhileBufferedUnderThreshold()
+        await queueSending()
+    }
+
+    function queueSending() {
+        sendQueueRef.current = sendQueueRef.current
+            .then(sendWhileBufferedUnderThreshold)
+            .catch(err => console.error('send failed', err))
+        return sendQueueRef.current
     }
 
     async function sendWhileBufferedUnderThreshold() {
@@ -124,7 +138,7 @@ export default function Transmitter() {
 
     async function allowMoreChunks() {
         chunksRef.current.allowedToSend += Math.max(chunksRef.current.chunks.length / 22, 1)
-        await sendWhileBufferedUnderThreshold()
+        await queueSending()
     }
 
     async function sendNextChunk() {
@@ -136,7 +150,7 @@ export default function Transmitter() {
             return
         }
 
-        const buf = chunks[sentChunks].buffer ? chunks[sentChunks].buffer : chunks[sentChunks]
+        const buf = await sealBytes(encodeFrame(sentChunks, chunksRef.current.sentBytes, chunks[sentChunks]), keyRef.current)
         try {
             dcRef.current.send(buf)
         } catch (err) {
@@ -150,7 +164,7 @@ export default function Transmitter() {
         setProgress(prev => ({ ...prev, sent: chunksRef.current.sentBytes }))
 
         if (chunksRef.current.sentChunks >= chunks.length) {
-            dcRef.current.send(JSON.stringify({ type: "end" }))
+            dcRef.current.send(await sealText(JSON.stringify({ type: "end" }), keyRef.current))
             setState('done')
         }
     }
@@ -94,4 +94,65 @@ export async function importKey(b64) {
     true,
     ["encrypt", "decrypt"]
   );
-}
\ No newline at end of file
+}
+
+// ========== Обмен ключами X25519 ==========
+// Зеркалит cli/src/crypto.rs: ключ сессии = HKDF-SHA256(X25519, salt = offerKey || answerKey)
+const SESSION_KEY_INFO = new TextEncoder().encode("instant-pastebin v1 session key");
+
+export async function generateKeyPair() {
+  return crypto.subtle.generateKey({ name: "X25519" }, true, ["deriveBits"]);
+}
+
+export async function exportPublicKey(keyPair) {
+  return toB64(new Uint8Array(await crypto.subtle.exportKey("raw", keyPair.publicKey)));
+}
+
+export async function deriveSessionKey(keyPair, peerPublicB64, offerPublicB64, answerPublicB64) {
+  const peerPublic = await crypto.subtle.importKey("raw", fromB64(peerPublicB64), { name: "X25519" }, true, []);
+  const shared = await crypto.subtle.deriveBits({ name: "X25519", public: peerPublic }, keyPair.privateKey, 256);
+  const hkdfKey = await crypto.subtle.importKey("raw", shared, "HKDF", false, ["deriveKey"]);
+
+  const offer = fromB64(offerPublicB64);
+  const answer = fromB64(answerPublicB64);
+  const salt = new Uint8Array(offer.length + answer.length);
+  salt.set(offer, 0);
+  salt.set(answer, offer.length);
+
+  return crypto.subtle.deriveKey(
+    { name: "HKDF", hash: "SHA-256", salt, info: SESSION_KEY_INFO },
+    hkdfKey,
+    { name: "AES-GCM", length: 256 },
+    true,
+    ["encrypt", "decrypt"]
+  );
+}
+
+// ========== Шифрование сообщений data channel ==========
+// Бинарные сообщения: iv || ct
+export async function sealBytes(bytes, key) {
+  const iv = crypto.getRandomValues(new Uint8Array(12));
+  const ct = new Uint8Array(await crypto.subtle.encrypt({ name: "AES-GCM", iv }, key, bytes));
+  const out = new Uint8Array(iv.length + ct.length);
+  out.set(iv, 0);
+  out.set(ct, iv.length);
+  return out;
+}
+
+export async function openBytes(data, key) {
+  const bytes = new Uint8Array(data);
+  const iv = bytes.subarray(0, 12);
+  const ct = bytes.subarray(12);
+  return new Uint8Array(await crypto.subtle.decrypt({ name: "AES-GCM", iv }, key, ct));
+}
+
+// Текстовые сообщения: один блок encryptBlocks в JSON
+export async function sealText(text, key) {
+  const bytes = new TextEncoder().encode(text);
+  const [block] = await encryptBlocks(bytes, key, bytes.length);
+  return JSON.stringify(block);
+}
+
+export async function openText(data, key) {
+  return new TextDecoder().decode(await decryptBlocks([JSON.parse(data)], key));
+}
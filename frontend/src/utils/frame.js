@@ -0,0 +1,34 @@
+// Binary chunk framing, mirrors `Frame` in cli/src/common.rs
+// version (1) + sequence (8) + offset (8) + length (4), big-endian
+export const FRAME_VERSION = 1
+export const FRAME_HEADER_LEN = 21
+
+export function encodeFrame(sequence, offset, payload) {
+  const out = new Uint8Array(FRAME_HEADER_LEN + payload.byteLength)
+  const view = new DataView(out.buffer)
+  view.setUint8(0, FRAME_VERSION)
+  view.setBigUint64(1, BigInt(sequence))
+  view.setBigUint64(9, BigInt(offset))
+  view.setUint32(17, payload.byteLength)
+  out.set(payload, FRAME_HEADER_LEN)
+  return out
+}
+
+export function decodeFrame(data) {
+  const bytes = new Uint8Array(data)
+  if (bytes.byteLength < FRAME_HEADER_LEN) {
+    throw new Error(`Frame too short: ${bytes.byteLength} bytes`)
+  }
+  const view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength)
+  const version = view.getUint8(0)
+  if (version !== FRAME_VERSION) {
+    throw new Error(`Unsupported frame version ${version}`)
+  }
+  const sequence = Number(view.getBigUint64(1))
+  const offset = Number(view.getBigUint64(9))
+  const length = view.getUint32(17)
+  if (bytes.byteLength - FRAME_HEADER_LEN !== length) {
+    throw new Error(`Frame length mismatch: header says ${length}, got ${bytes.byteLength - FRAME_HEADER_LEN}`)
+  }
+  return { sequence, offset, payload: bytes.subarray(FRAME_HEADER_LEN) }
+}